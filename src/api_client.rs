@@ -1,4 +1,9 @@
 use crate::errors::{Result, *};
+#[cfg(feature = "serde")]
+use crate::models::SteeringManifest;
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
 
 impl From<reqwest::Error> for Error {
     fn from(e: reqwest::Error) -> Self {
@@ -6,28 +11,91 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+/// The master playlist fetched when no other URL is given.
+pub(crate) const DEFAULT_PLAYLIST_URL: &str =
+    "https://lw.bamgrid.com/2.0/hls/vod/bam/ms02/hls/dplus/bao/master_unenc_hdr10_all.m3u8";
+
 /// A super simple blocking API client.
-/// Its only responsibility is to grab a master playlist from a very specific API endpoint.
-pub(crate) struct BlockingApiClient {}
+/// Its only responsibility is to grab playlists over HTTP. It holds on to its `reqwest` client
+/// so that connections can be reused across requests.
+pub struct BlockingApiClient {
+    client: Client,
+}
 
 /// This trait represents the behavior we need from an Api Client.
-pub(crate) trait ApiClient {
-    // This didn't end up being necessary since I didn't have time to use it but usually for
-    // testing purposes I like to put a mock here, hence the use of a trait!
-    /// Retrieve a master playlist and return its contents as a String.
-    fn get_master_playlist(&self) -> Result<String>;
+pub trait ApiClient {
+    /// Retrieve the playlist at the given URL and return its contents as a String.
+    fn get_playlist(&self, url: &str) -> Result<String>;
+
+    /// Retrieve the default master playlist and return its contents as a String.
+    fn get_master_playlist(&self) -> Result<String> {
+        self.get_playlist(DEFAULT_PLAYLIST_URL)
+    }
+
+    /// Retrieve and parse the steering manifest at the SERVER-URI of an EXT-X-CONTENT-STEERING
+    /// tag, or at the RELOAD-URI of the last manifest.
+    #[cfg(feature = "serde")]
+    fn get_steering_manifest(&self, url: &str) -> Result<SteeringManifest> {
+        SteeringManifest::from_json(&self.get_playlist(url)?)
+    }
+}
+
+impl BlockingApiClient {
+    /// Creates a client with no extra headers and reqwest's default timeout.
+    pub fn new() -> Result<Self> {
+        Self::builder().build()
+    }
+
+    /// Wraps an existing `reqwest` client, e.g. one shared with the rest of an application.
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn builder() -> BlockingApiClientBuilder {
+        BlockingApiClientBuilder::default()
+    }
 }
 
 impl ApiClient for BlockingApiClient {
-    /// Create a blocking HTTP client and get a master playlist.
-    /// Note: We don't bother creating and storing one because we're only making this request
-    /// one time -- if we were going to issue multiple requests it would be worth constructing
-    /// a client to reuse.
-    fn get_master_playlist(&self) -> Result<String> {
-        reqwest::blocking::get(
-            "https://lw.bamgrid.com/2.0/hls/vod/bam/ms02/hls/dplus/bao/master_unenc_hdr10_all.m3u8",
-        )?
-        .text()
-        .map_err(Into::into)
+    fn get_playlist(&self, url: &str) -> Result<String> {
+        self.client
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .text()
+            .map_err(Into::into)
+    }
+}
+
+/// Configures the headers and timeout used by a `BlockingApiClient`.
+#[derive(Default)]
+pub struct BlockingApiClientBuilder {
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+}
+
+impl BlockingApiClientBuilder {
+    /// Sends this header with every request, e.g. an auth token or a User-Agent.
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::HTTP(format!("Invalid header name {}: {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::HTTP(format!("Invalid value for header {}: {}", name, e)))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Gives up on a request once this much time has passed.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> Result<BlockingApiClient> {
+        let mut builder = Client::builder().default_headers(self.headers);
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        Ok(BlockingApiClient::with_client(builder.build()?))
     }
 }
@@ -0,0 +1,513 @@
+use hls_parser::models::{
+    validate, validate_media_playlist, ParseOptions, Playlist, Severity, SortBy,
+};
+use hls_parser::{parse_playlist_with_options, ApiClient, BlockingApiClient, Error};
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+
+pub(crate) const USAGE: &str = "\
+Usage: hls_parser [COMMAND] [OPTIONS] [SOURCE]
+
+Commands:
+  parse      Parse a playlist and print its structure (default)
+  validate   Check a playlist against the rules of the HLS spec
+  convert    Parse a playlist and write it back out
+
+Source:
+  An http(s) URL, a path to a local file or `-` to read from stdin.
+  Defaults to the example master playlist URL.
+
+Options:
+  -f, --format <FORMAT>  Output format: debug, m3u8 or json
+                         (parse defaults to debug, convert to m3u8,
+                         json needs the serde feature)
+  -s, --sort <KEYS>      Sort the variant streams, I-frames and media tags of a
+                         master playlist by a comma separated list of keys, each
+                         optionally followed by :asc or :desc. Keys: bandwidth,
+                         average-bandwidth, resolution, frame-rate, score, codecs,
+                         audio, and for media tags group, language, name
+      --preserve-order   Keep entries in the order they appear in the playlist
+      --lenient          Keep unknown enumerated values, such as a new VIDEO-RANGE,
+                         instead of failing (validate reports them as warnings)
+  -h, --help             Print this message
+
+Exit codes:
+  0 success, 1 I/O error, 2 usage error, 3 network error,
+  4 parse error, 5 invalid HLS, 6 playlist breaks the spec,
+  7 unsupported for this playlist or build, e.g. m3u8 output of a media playlist";
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Action {
+    Parse,
+    Validate,
+    Convert,
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Source {
+    /// The example master playlist the parser has always fetched.
+    Default,
+    Url(String),
+    File(String),
+    Stdin,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum OutputFormat {
+    Debug,
+    M3u8,
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Command {
+    pub(crate) action: Action,
+    pub(crate) source: Source,
+    pub(crate) format: Option<OutputFormat>,
+    pub(crate) sort: Vec<SortBy>,
+    pub(crate) preserve_order: bool,
+    pub(crate) lenient: bool,
+}
+
+#[derive(Debug)]
+pub(crate) enum CliError {
+    Usage(String),
+    Io(io::Error),
+    Hls(Error),
+    /// The playlist parsed but broke this many rules of the spec.
+    Invalid(usize),
+    /// The arguments are valid but can't be carried out for this playlist or build.
+    Unsupported(String),
+}
+
+impl CliError {
+    /// Maps each kind of failure to its own exit code so scripts can tell them apart.
+    pub(crate) fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Io(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Hls(Error::HTTP(_)) => 3,
+            CliError::Hls(Error::Parse { .. }) => 4,
+            CliError::Hls(Error::HLSFormat(_)) | CliError::Hls(Error::HLSTag { .. }) => 5,
+            CliError::Invalid(_) => 6,
+            CliError::Unsupported(_) => 7,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::Io(e) => write!(f, "I/O Error: {}", e),
+            CliError::Hls(e) => write!(f, "{}", e),
+            CliError::Invalid(errors) => write!(f, "Playlist has {} spec violation(s)", errors),
+            CliError::Unsupported(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io(e)
+    }
+}
+
+impl From<Error> for CliError {
+    fn from(e: Error) -> Self {
+        CliError::Hls(e)
+    }
+}
+
+impl OutputFormat {
+    fn from_arg(format: &str) -> Result<Self, CliError> {
+        match format {
+            "debug" => Ok(OutputFormat::Debug),
+            "m3u8" => Ok(OutputFormat::M3u8),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(CliError::Usage(format!(
+                "Unknown output format: {}",
+                format
+            ))),
+        }
+    }
+}
+
+impl Source {
+    fn from_arg(source: String) -> Self {
+        if source == "-" {
+            Source::Stdin
+        } else if source.starts_with("http://") || source.starts_with("https://") {
+            Source::Url(source)
+        } else {
+            Source::File(source)
+        }
+    }
+
+    fn read(&self) -> Result<String, CliError> {
+        Ok(match self {
+            Source::Default => BlockingApiClient::new()?.get_master_playlist()?,
+            Source::Url(url) => BlockingApiClient::new()?.get_playlist(url)?,
+            Source::File(path) => fs::read_to_string(path)?,
+            Source::Stdin => {
+                let mut hls_str = String::new();
+                io::stdin().read_to_string(&mut hls_str)?;
+                hls_str
+            }
+        })
+    }
+}
+
+/// Parses the arguments following the program name.
+pub(crate) fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, CliError> {
+    let mut args = args.into_iter().peekable();
+
+    let action = match args.peek().map(String::as_str) {
+        Some("parse") => Action::Parse,
+        Some("validate") => Action::Validate,
+        Some("convert") => Action::Convert,
+        // No subcommand given, so keep the arguments for the default parse command
+        _ => return parse_options(Action::Parse, args),
+    };
+    args.next();
+
+    parse_options(action, args)
+}
+
+fn parse_options(
+    action: Action,
+    mut args: impl Iterator<Item = String>,
+) -> Result<Command, CliError> {
+    let mut command = Command {
+        action,
+        source: Source::Default,
+        format: None,
+        sort: vec![],
+        preserve_order: false,
+        lenient: false,
+    };
+    let mut source = None;
+
+    while let Some(arg) = args.next() {
+        // Long options can give their value after an `=`, e.g. `--sort=bandwidth`
+        let (option, mut inline_value) = match arg.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => (option, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .take()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError::Usage(format!("{} requires a value", option)))
+        };
+
+        match option {
+            "-f" | "--format" => command.format = Some(OutputFormat::from_arg(&value()?)?),
+            "-s" | "--sort" => {
+                command.sort = value()?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .map_err(CliError::Usage)?;
+            }
+            _ if inline_value.is_some() => {
+                return Err(CliError::Usage(format!("{} doesn't take a value", option)))
+            }
+            "-h" | "--help" => command.action = Action::Help,
+            "--preserve-order" => command.preserve_order = true,
+            "--lenient" => command.lenient = true,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(CliError::Usage(format!("Unknown option: {}", arg)))
+            }
+            _ if source.is_some() => {
+                return Err(CliError::Usage(format!("Unexpected argument: {}", arg)))
+            }
+            _ => source = Some(Source::from_arg(arg)),
+        }
+    }
+
+    if let Some(source) = source {
+        command.source = source;
+    }
+    Ok(command)
+}
+
+fn write_playlist(
+    playlist: &Playlist,
+    format: OutputFormat,
+    mut out: impl Write,
+) -> Result<(), CliError> {
+    match (format, playlist) {
+        (OutputFormat::Debug, playlist) => writeln!(out, "{:#?}", playlist)?,
+        (OutputFormat::M3u8, Playlist::Master(master_playlist)) => {
+            master_playlist.write_to(&mut out)?
+        }
+        (OutputFormat::M3u8, Playlist::Media(_)) => {
+            return Err(CliError::Unsupported(
+                "m3u8 output is only supported for master playlists".to_string(),
+            ))
+        }
+        (OutputFormat::Json, playlist) => write_json(playlist, &mut out)?,
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn write_json(playlist: &Playlist, mut out: impl Write) -> Result<(), CliError> {
+    match playlist {
+        Playlist::Master(master_playlist) => {
+            serde_json::to_writer_pretty(&mut out, master_playlist).map_err(io::Error::from)?
+        }
+        Playlist::Media(media_playlist) => {
+            serde_json::to_writer_pretty(&mut out, media_playlist).map_err(io::Error::from)?
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn write_json(_playlist: &Playlist, _out: impl Write) -> Result<(), CliError> {
+    Err(CliError::Unsupported(
+        "json output needs hls_parser to be built with the serde feature".to_string(),
+    ))
+}
+
+/// Runs a command, writing its output to `out`.
+pub(crate) fn run(command: Command, mut out: impl Write) -> Result<(), CliError> {
+    if command.action == Action::Help {
+        writeln!(out, "{}", USAGE)?;
+        return Ok(());
+    }
+
+    let url = match &command.source {
+        Source::Url(url) => Some(url.clone()),
+        _ => None,
+    };
+    let options = ParseOptions {
+        preserve_order: command.preserve_order,
+        lenient: command.lenient,
+        url,
+        ..ParseOptions::default()
+    };
+    let mut playlist = parse_playlist_with_options(&command.source.read()?, &options)?;
+    if !command.sort.is_empty() {
+        match &mut playlist {
+            Playlist::Master(master_playlist) => {
+                master_playlist.sort_variant_streams(&command.sort);
+                master_playlist.sort_i_frames(&command.sort);
+                master_playlist.sort_media_tags(&command.sort);
+            }
+            Playlist::Media(_) => {
+                return Err(CliError::Unsupported(
+                    "--sort only applies to master playlists".to_string(),
+                ))
+            }
+        }
+    }
+
+    match command.action {
+        Action::Parse => write_playlist(
+            &playlist,
+            command.format.unwrap_or(OutputFormat::Debug),
+            out,
+        ),
+        Action::Convert => {
+            write_playlist(&playlist, command.format.unwrap_or(OutputFormat::M3u8), out)
+        }
+        Action::Validate => {
+            let (kind, diagnostics) = match &playlist {
+                Playlist::Master(master_playlist) => ("master", validate(master_playlist)),
+                Playlist::Media(media_playlist) => {
+                    ("media", validate_media_playlist(media_playlist))
+                }
+            };
+            for diagnostic in &diagnostics {
+                writeln!(out, "{}", diagnostic)?;
+            }
+            let errors = diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .count();
+            if errors > 0 {
+                return Err(CliError::Invalid(errors));
+            }
+            writeln!(out, "Valid {} playlist", kind)?;
+            Ok(())
+        }
+        Action::Help => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hls_parser::models::SortKey;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn defaults_to_parsing_the_default_playlist() {
+        assert_eq!(
+            parse_args(args(&[])).unwrap(),
+            Command {
+                action: Action::Parse,
+                source: Source::Default,
+                format: None,
+                sort: vec![],
+                preserve_order: false,
+                lenient: false,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_subcommand_source_and_format() {
+        assert_eq!(
+            parse_args(args(&["convert", "--format", "m3u8", "master.m3u8"])).unwrap(),
+            Command {
+                action: Action::Convert,
+                source: Source::File("master.m3u8".to_string()),
+                format: Some(OutputFormat::M3u8),
+                sort: vec![],
+                preserve_order: false,
+                lenient: false,
+            }
+        );
+        assert_eq!(
+            parse_args(args(&["validate", "-"])).unwrap().source,
+            Source::Stdin
+        );
+        assert_eq!(
+            parse_args(args(&["https://example.com/master.m3u8"]))
+                .unwrap()
+                .source,
+            Source::Url("https://example.com/master.m3u8".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_sort_keys() {
+        let command = parse_args(args(&["--sort", "bandwidth:desc,resolution", "-"])).unwrap();
+
+        assert_eq!(
+            command.sort,
+            vec![
+                SortBy::descending(SortKey::Bandwidth),
+                SortBy::ascending(SortKey::Resolution)
+            ]
+        );
+        assert!(parse_args(args(&["--sort", "colour"])).is_err());
+    }
+
+    #[test]
+    fn accepts_option_values_after_an_equals_sign() {
+        let command = parse_args(args(&["--sort=bandwidth:desc", "--format=m3u8", "-"])).unwrap();
+
+        assert_eq!(command.sort, vec![SortBy::descending(SortKey::Bandwidth)]);
+        assert_eq!(command.format, Some(OutputFormat::M3u8));
+        assert!(parse_args(args(&["--lenient=yes"])).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        let error = parse_args(args(&["parse", "--colour"])).unwrap_err();
+
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn uses_distinct_exit_codes_for_each_error() {
+        let codes: Vec<i32> = [
+            CliError::Hls(Error::HTTP("timeout".to_string())),
+            CliError::Hls(Error::HLSFormat("HLS missing BANDWIDTH".to_string())),
+            CliError::Io(io::Error::new(io::ErrorKind::NotFound, "missing")),
+            CliError::Unsupported("m3u8 output".to_string()),
+        ]
+        .iter()
+        .map(CliError::exit_code)
+        .collect();
+
+        assert_eq!(codes, vec![3, 5, 1, 7]);
+    }
+
+    #[test]
+    fn reports_what_media_playlists_dont_support() {
+        // Unique to this process and test so that concurrent runs don't share the file
+        let path = std::env::temp_dir().join(format!(
+            "hls_parser_{}_reports_what_media_playlists_dont_support.m3u8",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:9.009,\nfirst.ts\n#EXT-X-ENDLIST\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+
+        let convert_error = run(parse_args(args(&["convert", path])).unwrap(), Vec::new());
+        let sort_error = run(
+            parse_args(args(&["parse", "--sort", "bandwidth", path])).unwrap(),
+            Vec::new(),
+        );
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(convert_error.unwrap_err().exit_code(), 7);
+        assert_eq!(sort_error.unwrap_err().exit_code(), 7);
+    }
+
+    #[test]
+    fn validate_lists_spec_violations() {
+        // Unique to this process and test so that concurrent runs don't share the file
+        let path = std::env::temp_dir().join(format!(
+            "hls_parser_{}_validate_lists_spec_violations.m3u8",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "#EXT-X-STREAM-INF:BANDWIDTH=1000,AUDIO=\"aac\"\nlow/vod.m3u8\n",
+        )
+        .unwrap();
+        let command = parse_args(args(&["validate", path.to_str().unwrap()])).unwrap();
+        let mut out = Vec::new();
+
+        let error = run(command, &mut out).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(error.exit_code(), 6);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "error[extm3u-first]: Playlist does not start with #EXTM3U\n\
+             error[group-reference]: Variant stream low/vod.m3u8 refers to AUDIO group \"aac\" but there is no such EXT-X-MEDIA\n"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn writes_playlists_as_json() {
+        let playlist = hls_parser::parse_playlist(
+            "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1000,RESOLUTION=640x360\nlow/vod.m3u8\n",
+        )
+        .unwrap();
+        let mut out = Vec::new();
+
+        write_playlist(&playlist, OutputFormat::Json, &mut out).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["variant_streams"][0]["uri"], "low/vod.m3u8");
+        assert_eq!(json["variant_streams"][0]["resolution"]["height"], 360);
+    }
+
+    #[test]
+    fn reports_missing_files_as_io_errors() {
+        let command = parse_args(args(&["parse", "does/not/exist.m3u8"])).unwrap();
+
+        let error = run(command, Vec::new()).unwrap_err();
+
+        assert_eq!(error.exit_code(), 1);
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use thiserror::Error;
 
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
@@ -6,8 +7,67 @@ pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 pub enum Error {
     #[error("API Error: {0}")]
     HTTP(String),
-    #[error("Parse Error: {0}")]
-    Parse(String),
+    #[error("Parse Error at {location}: {message}")]
+    Parse { message: String, location: Location },
     #[error("Invalid HLS: {0}")]
     HLSFormat(String),
+    #[error("Invalid HLS in #{tag} at {location}: {message}")]
+    HLSTag {
+        tag: String,
+        message: String,
+        location: Location,
+    },
+}
+
+impl Error {
+    /// Attaches the tag being converted, and where it was found, to a format error.
+    pub(crate) fn in_tag(self, tag: &str, location: &Location) -> Self {
+        match self {
+            Error::HLSFormat(message) => Error::HLSTag {
+                tag: tag.to_string(),
+                message,
+                location: location.clone(),
+            },
+            other => other,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+/// A position within the source playlist, along with the line found there.
+pub struct Location {
+    /// The line number, starting from 1.
+    pub line: usize,
+    /// The column number in characters, starting from 1.
+    pub column: usize,
+    /// The full source line this location points into.
+    pub snippet: String,
+}
+
+impl Location {
+    /// Works out the location of `position`, which must be a suffix of `source`.
+    pub(crate) fn new(source: &str, position: &str) -> Self {
+        let consumed = &source[..source.len() - position.len()];
+        let line_start = consumed.rfind('\n').map_or(0, |index| index + 1);
+
+        Self {
+            line: consumed.matches('\n').count() + 1,
+            column: consumed[line_start..].chars().count() + 1,
+            snippet: source[line_start..]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {} (`{}`)",
+            self.line, self.column, self.snippet
+        )
+    }
 }
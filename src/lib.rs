@@ -1,27 +1,73 @@
+// HLS is full of acronyms (PQ, SDR, HTTP) and we keep their spec spelling
+#![allow(clippy::upper_case_acronyms)]
+
 mod api_client;
 mod errors;
 pub mod models;
 mod parser;
 
-use crate::api_client::{ApiClient, BlockingApiClient};
+pub use crate::api_client::{ApiClient, BlockingApiClient, BlockingApiClientBuilder};
+pub use crate::errors::{Error, Location};
+
+use crate::api_client::DEFAULT_PLAYLIST_URL;
 use crate::errors::Result;
-use crate::models::MasterPlaylist;
-use crate::parser::HLSParser;
-use std::convert::TryInto;
+use crate::models::{MasterPlaylist, ParseOptions, Playlist};
+use crate::parser::{substitute_variables, HLSParser, Tag, VariableSources};
+
+/// Fetches the example master playlist and parses it.
+pub fn parse_default_hls() -> Result<MasterPlaylist> {
+    parse_hls_from_url(DEFAULT_PLAYLIST_URL)
+}
 
-pub fn parse_default_hls() -> Result<()> {
-    let api_client = BlockingApiClient {};
-    let hls_str = &api_client
-        .get_master_playlist()
-        .expect("Failed to get HLS playlist");
-    let parsed = parse_hls(hls_str);
-    println!("{:#?}", parsed);
-    Ok(())
+/// Fetches the master playlist at the given URL and parses it.
+pub fn parse_hls_from_url(url: &str) -> Result<MasterPlaylist> {
+    parse_hls_from_url_with_client(url, &BlockingApiClient::new()?)
+}
+
+/// Fetches the master playlist at the given URL with the given client, e.g. one built with extra
+/// headers or a timeout, and parses it.
+pub fn parse_hls_from_url_with_client(
+    url: &str,
+    api_client: &impl ApiClient,
+) -> Result<MasterPlaylist> {
+    let hls_str = api_client.get_playlist(url)?;
+    let options = ParseOptions {
+        url: Some(url.to_string()),
+        ..ParseOptions::default()
+    };
+    parse_hls_with_options(&hls_str, &options)
 }
 
 pub fn parse_hls(hls_str: &str) -> Result<MasterPlaylist> {
+    parse_hls_with_options(hls_str, &ParseOptions::default())
+}
+
+pub fn parse_hls_with_options(hls_str: &str, options: &ParseOptions) -> Result<MasterPlaylist> {
+    MasterPlaylist::from_tags(parse_tags(hls_str, options)?, options)
+}
+
+/// Parses either a master or a media playlist, detecting which kind it is from its tags.
+pub fn parse_playlist(hls_str: &str) -> Result<Playlist> {
+    parse_playlist_with_options(hls_str, &ParseOptions::default())
+}
+
+pub fn parse_playlist_with_options(hls_str: &str, options: &ParseOptions) -> Result<Playlist> {
+    Playlist::from_tags(parse_tags(hls_str, options)?, options)
+}
+
+/// Parses a playlist into its tags and substitutes any EXT-X-DEFINE variables into them.
+fn parse_tags(hls_str: &str, options: &ParseOptions) -> Result<Vec<Tag>> {
     let parser = HLSParser {};
 
-    let parsed = parser.parse(hls_str)?;
-    parsed.try_into()
+    let mut parsed = parser.parse(hls_str)?;
+    let media_playlist = Playlist::is_media_playlist(&parsed);
+    substitute_variables(
+        &mut parsed,
+        &VariableSources {
+            imports: &options.imported_variables,
+            url: options.url.as_deref(),
+            media_playlist,
+        },
+    )?;
+    Ok(parsed)
 }
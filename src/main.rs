@@ -1,7 +1,14 @@
-use hls_parser::parse_default_hls;
+mod cli;
+
+use std::io;
+use std::process;
 
 fn main() {
-    if let Err(e) = parse_default_hls() {
-        println!("Error: {:?}", e);
+    let result = cli::parse_args(std::env::args().skip(1))
+        .and_then(|command| cli::run(command, io::stdout().lock()));
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        process::exit(e.exit_code());
     }
 }
@@ -0,0 +1,329 @@
+use std::fmt::{self, Display, Formatter};
+use std::slice;
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "String", into = "String")
+)]
+/// The comma separated list of formats from a CODECS attribute, as described in
+/// [RFC 6381](https://tools.ietf.org/html/rfc6381).
+pub struct Codecs(Vec<Codec>);
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone)]
+/// A single entry of a CODECS attribute, e.g. `hvc1.2.4.L63.90`.
+pub struct Codec {
+    /// The entry exactly as it appeared in the playlist.
+    raw: String,
+    format: CodecFormat,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// What a codec entry decodes to. Entries that aren't recognised, or don't follow the format
+/// of their codec, are `Unknown` and only available as the raw string.
+pub enum CodecFormat {
+    Avc(Avc),
+    Hevc(Hevc),
+    Aac(AacObjectType),
+    Ac3,
+    Ec3,
+    Opus,
+    Av1(Av1),
+    Vp9(Vp9),
+    DolbyVision(DolbyVision),
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// A codec level such as 4.1.
+pub struct Level {
+    pub major: u8,
+    pub minor: u8,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// The tier of an HEVC or AV1 stream, which caps its bit rate within a level.
+pub enum Tier {
+    Main,
+    High,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// H.264, from `avc1.PPCCLL` where each pair is a hex byte.
+pub struct Avc {
+    pub profile: AvcProfile,
+    /// The constraint_set flags byte.
+    pub constraints: u8,
+    pub level: Level,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+pub enum AvcProfile {
+    Baseline,
+    Main,
+    Extended,
+    High,
+    High10,
+    High422,
+    High444,
+    Other(u8),
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// H.265, from `hvc1.<profile>.<compatibility>.<tier and level>.<constraints>`.
+pub struct Hevc {
+    pub profile: HevcProfile,
+    pub tier: Tier,
+    pub level: Level,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+pub enum HevcProfile {
+    Main,
+    Main10,
+    MainStillPicture,
+    RangeExtensions,
+    Other(u8),
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// The MPEG-4 audio object type from `mp4a.40.<object type>`.
+pub enum AacObjectType {
+    Main,
+    Lc,
+    HeAac,
+    HeAacV2,
+    Other(u8),
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// AV1, from `av01.<profile>.<level><tier>.<bit depth>`.
+pub struct Av1 {
+    pub profile: u8,
+    pub level: Level,
+    pub tier: Tier,
+    pub bit_depth: u8,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// VP9, from `vp09.<profile>.<level>.<bit depth>`.
+pub struct Vp9 {
+    pub profile: u8,
+    pub level: Level,
+    pub bit_depth: u8,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+/// Dolby Vision, from e.g. `dvh1.<profile>.<level>`.
+pub struct DolbyVision {
+    pub profile: u8,
+    /// Dolby Vision levels are a plain index rather than a major.minor number.
+    pub level: u8,
+}
+
+impl Codecs {
+    pub fn iter(&self) -> slice::Iter<'_, Codec> {
+        self.0.iter()
+    }
+
+    /// The decoded format of every entry in the list.
+    pub fn formats(&self) -> impl Iterator<Item = CodecFormat> + '_ {
+        self.0.iter().map(Codec::format)
+    }
+}
+
+impl Codec {
+    /// The entry exactly as it appeared in the playlist.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn format(&self) -> CodecFormat {
+        self.format
+    }
+
+    /// Compatibility brands following the format, as used by SUPPLEMENTAL-CODECS,
+    /// e.g. `db4h` in `dvh1.08.07/db4h`.
+    pub fn brands(&self) -> impl Iterator<Item = &str> {
+        self.raw.split('/').skip(1)
+    }
+}
+
+impl Level {
+    pub fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// AVC and VP9 write levels as ten times their value, e.g. 31 for 3.1.
+    fn from_tenths(level: u8) -> Self {
+        Self::new(level / 10, level % 10)
+    }
+}
+
+impl From<&str> for Codecs {
+    fn from(codecs: &str) -> Self {
+        Self(codecs.split(',').map(|codec| codec.trim().into()).collect())
+    }
+}
+
+impl From<String> for Codecs {
+    fn from(codecs: String) -> Self {
+        codecs.as_str().into()
+    }
+}
+
+impl From<Codecs> for String {
+    fn from(codecs: Codecs) -> Self {
+        codecs.to_string()
+    }
+}
+
+impl From<&str> for Codec {
+    fn from(codec: &str) -> Self {
+        Self {
+            raw: codec.to_string(),
+            format: decode(codec.split('/').next().unwrap_or_default())
+                .unwrap_or(CodecFormat::Unknown),
+        }
+    }
+}
+
+impl From<u8> for AvcProfile {
+    fn from(profile_idc: u8) -> Self {
+        match profile_idc {
+            66 => AvcProfile::Baseline,
+            77 => AvcProfile::Main,
+            88 => AvcProfile::Extended,
+            100 => AvcProfile::High,
+            110 => AvcProfile::High10,
+            122 => AvcProfile::High422,
+            244 => AvcProfile::High444,
+            other => AvcProfile::Other(other),
+        }
+    }
+}
+
+impl From<u8> for HevcProfile {
+    fn from(profile_idc: u8) -> Self {
+        match profile_idc {
+            1 => HevcProfile::Main,
+            2 => HevcProfile::Main10,
+            3 => HevcProfile::MainStillPicture,
+            4 => HevcProfile::RangeExtensions,
+            other => HevcProfile::Other(other),
+        }
+    }
+}
+
+impl From<u8> for AacObjectType {
+    fn from(object_type: u8) -> Self {
+        match object_type {
+            1 => AacObjectType::Main,
+            2 => AacObjectType::Lc,
+            5 => AacObjectType::HeAac,
+            29 => AacObjectType::HeAacV2,
+            other => AacObjectType::Other(other),
+        }
+    }
+}
+
+impl Display for Codecs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let raw: Vec<&str> = self.0.iter().map(Codec::raw).collect();
+        write!(f, "{}", raw.join(","))
+    }
+}
+
+impl Display for Codec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Decodes a single entry, giving up with `None` on anything that doesn't fit its codec's format.
+fn decode(codec: &str) -> Option<CodecFormat> {
+    let mut parts = codec.split('.');
+    let format = match parts.next()? {
+        "avc1" | "avc3" => CodecFormat::Avc(decode_avc(parts.next()?)?),
+        "hvc1" | "hev1" => CodecFormat::Hevc(decode_hevc(&mut parts)?),
+        // 40 is the object type indication for MPEG-4 audio
+        "mp4a" if parts.next()? == "40" => {
+            CodecFormat::Aac(parts.next()?.parse::<u8>().ok()?.into())
+        }
+        "ac-3" => CodecFormat::Ac3,
+        "ec-3" => CodecFormat::Ec3,
+        "Opus" | "opus" => CodecFormat::Opus,
+        "av01" => CodecFormat::Av1(decode_av1(&mut parts)?),
+        "vp09" => CodecFormat::Vp9(Vp9 {
+            profile: parts.next()?.parse().ok()?,
+            level: Level::from_tenths(parts.next()?.parse().ok()?),
+            bit_depth: parts.next()?.parse().ok()?,
+        }),
+        "dvh1" | "dvhe" | "dav1" | "dva1" | "dvav" => CodecFormat::DolbyVision(DolbyVision {
+            profile: parts.next()?.parse().ok()?,
+            level: parts.next()?.parse().ok()?,
+        }),
+        _ => return None,
+    };
+    Some(format)
+}
+
+fn decode_avc(profile_and_level: &str) -> Option<Avc> {
+    if profile_and_level.len() != 6 {
+        return None;
+    }
+    let byte = |index: usize| u8::from_str_radix(profile_and_level.get(index..index + 2)?, 16).ok();
+
+    Some(Avc {
+        profile: byte(0)?.into(),
+        constraints: byte(2)?,
+        level: Level::from_tenths(byte(4)?),
+    })
+}
+
+fn decode_hevc<'a>(parts: &mut impl Iterator<Item = &'a str>) -> Option<Hevc> {
+    // The profile may be prefixed with a profile space of A, B or C
+    let profile = parts.next()?.trim_start_matches(['A', 'B', 'C']);
+    // Skip over the compatibility flags
+    parts.next()?;
+    let tier_and_level = parts.next()?;
+    let tier = match tier_and_level.get(..1)? {
+        "L" => Tier::Main,
+        "H" => Tier::High,
+        _ => return None,
+    };
+    // level_idc is thirty times the level
+    let level_idc: u8 = tier_and_level[1..].parse().ok()?;
+
+    Some(Hevc {
+        profile: profile.parse::<u8>().ok()?.into(),
+        tier,
+        level: Level::new(level_idc / 30, level_idc % 30 / 3),
+    })
+}
+
+fn decode_av1<'a>(parts: &mut impl Iterator<Item = &'a str>) -> Option<Av1> {
+    let profile = parts.next()?.parse().ok()?;
+    let level_and_tier = parts.next()?;
+    let tier = match level_and_tier.get(2..)? {
+        "M" => Tier::Main,
+        "H" => Tier::High,
+        _ => return None,
+    };
+    // seq_level_idx counts up from level 2.0 in steps of a minor level, with four per major
+    let seq_level_idx: u8 = level_and_tier[..2].parse().ok()?;
+
+    Some(Av1 {
+        profile,
+        level: Level::new(2 + seq_level_idx / 4, seq_level_idx % 4),
+        tier,
+        bit_depth: parts.next()?.parse().ok()?,
+    })
+}
@@ -1,9 +1,16 @@
 use crate::errors::{Error, Result};
 use crate::models::{
-    IFrame, MasterPlaylist, MediaTag, MediaType, Resolution, VariantStream, VideoRange,
+    AllowedCpc, ByteRange, Channels, Codecs, ContentSteering, DateRange, FrameRate, HdcpLevel,
+    IFrame, InitializationVector, InstreamId, Key, KeyMethod, Map, MasterPlaylist, MediaPlaylist,
+    MediaTag, MediaType, ParseOptions, Part, Playlist, PlaylistType, PreloadHint, PreloadHintType,
+    RenditionReport, Resolution, Score, Segment, ServerControl, SessionData, SessionDataFormat,
+    SessionKey, Skip, SourceLine, SpliceInfoSection, UnknownTag, VariantStream, VideoLayout,
+    VideoRange,
 };
-use std::collections::HashMap;
+use crate::parser::Tag;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::{TryFrom, TryInto};
+use std::ops::RangeInclusive;
 
 impl TryFrom<String> for Resolution {
     type Error = Error;
@@ -29,6 +36,162 @@ impl TryFrom<String> for Resolution {
     }
 }
 
+impl TryFrom<f64> for FrameRate {
+    type Error = Error;
+
+    fn try_from(frame_rate: f64) -> Result<Self> {
+        let millis = (frame_rate * 1000.0).round();
+        if !(millis > 0.0 && millis <= f64::from(u32::MAX)) {
+            return Err(Error::HLSFormat(format!(
+                "Invalid frame rate specified: {}",
+                frame_rate
+            )));
+        }
+        Ok(Self(millis as u32))
+    }
+}
+
+impl From<FrameRate> for f64 {
+    fn from(frame_rate: FrameRate) -> Self {
+        frame_rate.as_f64()
+    }
+}
+
+impl TryFrom<&String> for Channels {
+    type Error = Error;
+
+    fn try_from(channels: &String) -> Result<Self> {
+        let mut parameters = channels.split('/');
+        let count = parameters.next().unwrap_or_default().parse().map_err(|_| {
+            Error::HLSFormat(format!("Invalid channel count specified: {}", channels))
+        })?;
+        // Each later parameter is a comma separated list, where `-` stands in for an empty one
+        let mut identifiers = || -> Vec<String> {
+            parameters
+                .next()
+                .filter(|identifiers| *identifiers != "-")
+                .map(|identifiers| identifiers.split(',').map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+
+        Ok(Self {
+            count,
+            spatial_audio: identifiers(),
+            special_usage: identifiers(),
+        })
+    }
+}
+
+impl TryFrom<f64> for Score {
+    type Error = Error;
+
+    fn try_from(score: f64) -> Result<Self> {
+        if !(score >= 0.0 && score.is_finite()) {
+            return Err(Error::HLSFormat(format!(
+                "Invalid score specified: {}",
+                score
+            )));
+        }
+        Ok(Self(score))
+    }
+}
+
+impl From<Score> for f64 {
+    fn from(score: Score) -> Self {
+        score.0
+    }
+}
+
+impl TryFrom<&String> for HdcpLevel {
+    type Error = Error;
+
+    fn try_from(hdcp_level: &String) -> Result<Self> {
+        match hdcp_level.as_str() {
+            "TYPE-0" => Ok(HdcpLevel::Type0),
+            "TYPE-1" => Ok(HdcpLevel::Type1),
+            "NONE" => Ok(HdcpLevel::None),
+            _ => Err(Error::HLSFormat(format!(
+                "Invalid HDCP level specified: {}",
+                hdcp_level
+            ))),
+        }
+    }
+}
+
+impl TryFrom<&str> for AllowedCpc {
+    type Error = Error;
+
+    fn try_from(allowed_cpc: &str) -> Result<Self> {
+        let (key_format, cpc) = allowed_cpc.split_once(':').ok_or(Error::HLSFormat(format!(
+            "ALLOWED-CPC entry is missing its KEYFORMAT: {}",
+            allowed_cpc
+        )))?;
+
+        Ok(Self {
+            key_format: key_format.to_string(),
+            cpc: cpc.split('/').map(str::to_string).collect(),
+        })
+    }
+}
+
+impl From<&str> for VideoLayout {
+    fn from(layout: &str) -> Self {
+        match layout {
+            "CH-STEREO" => VideoLayout::ChStereo,
+            "CH-MONO" => VideoLayout::ChMono,
+            other => VideoLayout::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for VideoLayout {
+    fn from(layout: String) -> Self {
+        layout.as_str().into()
+    }
+}
+
+impl From<VideoLayout> for String {
+    fn from(layout: VideoLayout) -> Self {
+        layout.to_string()
+    }
+}
+
+impl TryFrom<&String> for InstreamId {
+    type Error = Error;
+
+    fn try_from(instream_id: &String) -> Result<Self> {
+        let number_in = |number: &str, range: RangeInclusive<u8>| {
+            number.parse().ok().filter(|number| range.contains(number))
+        };
+        let parsed = if let Some(channel) = instream_id.strip_prefix("CC") {
+            number_in(channel, 1..=4).map(InstreamId::Cc)
+        } else if let Some(service) = instream_id.strip_prefix("SERVICE") {
+            number_in(service, 1..=63).map(InstreamId::Service)
+        } else {
+            None
+        };
+
+        parsed.ok_or(Error::HLSFormat(format!(
+            "Invalid INSTREAM-ID specified: {}",
+            instream_id
+        )))
+    }
+}
+
+impl TryFrom<String> for InstreamId {
+    type Error = Error;
+
+    fn try_from(instream_id: String) -> Result<Self> {
+        Self::try_from(&instream_id)
+    }
+}
+
+impl From<InstreamId> for String {
+    fn from(instream_id: InstreamId) -> Self {
+        instream_id.to_string()
+    }
+}
+
 impl TryFrom<&String> for MediaType {
     type Error = Error;
 
@@ -53,6 +216,7 @@ impl TryFrom<&String> for VideoRange {
         match video_range.as_str() {
             "PQ" => Ok(VideoRange::PQ),
             "SDR" => Ok(VideoRange::SDR),
+            "HLG" => Ok(VideoRange::HLG),
             _ => Err(Error::HLSFormat(format!(
                 "Invalid video range specified: {}",
                 video_range
@@ -61,15 +225,67 @@ impl TryFrom<&String> for VideoRange {
     }
 }
 
+impl From<String> for VideoRange {
+    /// Keeps values this parser doesn't know about as `Other`.
+    fn from(video_range: String) -> Self {
+        VideoRange::try_from(&video_range).unwrap_or(VideoRange::Other(video_range))
+    }
+}
+
+impl From<VideoRange> for String {
+    fn from(video_range: VideoRange) -> Self {
+        video_range.to_string()
+    }
+}
+
+impl VideoRange {
+    /// Converts a VIDEO-RANGE, which only falls back to `Other` in lenient mode.
+    fn with_options(video_range: &String, options: &ParseOptions) -> Result<Self> {
+        if options.lenient {
+            Ok(video_range.clone().into())
+        } else {
+            Self::try_from(video_range)
+        }
+    }
+}
+
+impl TryFrom<&String> for PlaylistType {
+    type Error = Error;
+
+    fn try_from(playlist_type: &String) -> Result<Self> {
+        match playlist_type.as_str() {
+            "EVENT" => Ok(PlaylistType::Event),
+            "VOD" => Ok(PlaylistType::Vod),
+            _ => Err(Error::HLSFormat(format!(
+                "Invalid playlist type specified: {}",
+                playlist_type
+            ))),
+        }
+    }
+}
+
 impl TryFrom<HashMap<String, String>> for MediaTag {
     type Error = Error;
 
     fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        let media_type: MediaType = attributes
+            .get("TYPE")
+            .ok_or(Error::HLSFormat("HLS missing TYPE".to_string()))?
+            .try_into()?;
+        let is_closed_captions = media_type == MediaType::ClosedCaptions;
+        if is_closed_captions != attributes.contains_key("INSTREAM-ID") {
+            return Err(Error::HLSFormat(if is_closed_captions {
+                "HLS missing INSTREAM-ID, which CLOSED-CAPTIONS renditions require".to_string()
+            } else {
+                format!(
+                    "INSTREAM-ID is only allowed for CLOSED-CAPTIONS, not {}",
+                    media_type
+                )
+            }));
+        }
+
         Ok(Self {
-            media_type: attributes
-                .get("TYPE")
-                .ok_or(Error::HLSFormat("HLS missing TYPE".to_string()))?
-                .try_into()?,
+            media_type,
             group_id: attributes
                 .get("GROUP-ID")
                 .ok_or(Error::HLSFormat("HLS missing GROUP-ID".to_string()))?
@@ -78,26 +294,203 @@ impl TryFrom<HashMap<String, String>> for MediaTag {
                 .get("NAME")
                 .ok_or(Error::HLSFormat("HLS missing NAME".to_string()))?
                 .clone(),
-            language: attributes
-                .get("LANGUAGE")
-                .ok_or(Error::HLSFormat("HLS missing LANGUAGE".to_string()))?
-                .clone(),
-            default: attributes
-                .get("DEFAULT")
-                .ok_or(Error::HLSFormat("HLS missing DEFAULT".to_string()))?
-                .clone(),
-            autoselect: attributes
-                .get("AUTOSELECT")
-                .ok_or(Error::HLSFormat("HLS missing AUTOSELECT".to_string()))?
-                .clone(),
+            language: attributes.get("LANGUAGE").cloned(),
+            assoc_language: attributes.get("ASSOC-LANGUAGE").cloned(),
+            stable_rendition_id: attributes.get("STABLE-RENDITION-ID").cloned(),
+            default: yes_no(&attributes, "DEFAULT")?,
+            autoselect: yes_no(&attributes, "AUTOSELECT")?,
+            forced: yes_no(&attributes, "FORCED")?,
+            instream_id: attributes
+                .get("INSTREAM-ID")
+                .map(InstreamId::try_from)
+                .transpose()?,
+            characteristics: attributes
+                .get("CHARACTERISTICS")
+                .map(|characteristics| characteristics.split(',').map(str::to_string).collect()),
             channels: attributes
                 .get("CHANNELS")
-                .ok_or(Error::HLSFormat("HLS missing CHANNELS".to_string()))?
+                .map(Channels::try_from)
+                .transpose()?,
+            uri: attributes.get("URI").cloned(),
+            line: SourceLine::default(),
+        })
+    }
+}
+
+impl TryFrom<&String> for SessionDataFormat {
+    type Error = Error;
+
+    fn try_from(format: &String) -> Result<Self> {
+        match format.as_str() {
+            "JSON" => Ok(SessionDataFormat::Json),
+            "RAW" => Ok(SessionDataFormat::Raw),
+            _ => Err(Error::HLSFormat(format!(
+                "Invalid session data format specified: {}",
+                format
+            ))),
+        }
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for SessionData {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            data_id: attributes
+                .get("DATA-ID")
+                .ok_or(Error::HLSFormat("HLS missing DATA-ID".to_string()))?
                 .clone(),
+            value: attributes.get("VALUE").cloned(),
+            uri: attributes.get("URI").cloned(),
+            language: attributes.get("LANGUAGE").cloned(),
+            format: attributes
+                .get("FORMAT")
+                .map(SessionDataFormat::try_from)
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<&String> for ByteRange {
+    type Error = Error;
+
+    fn try_from(byte_range: &String) -> Result<Self> {
+        let (length, offset) = match byte_range.split_once('@') {
+            Some((length, offset)) => (length, Some(offset.parse()?)),
+            None => (byte_range.as_str(), None),
+        };
+        Ok(Self {
+            length: length.parse()?,
+            offset,
+        })
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Map {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
             uri: attributes
                 .get("URI")
                 .ok_or(Error::HLSFormat("HLS missing URI".to_string()))?
                 .clone(),
+            byte_range: attributes
+                .get("BYTERANGE")
+                .map(ByteRange::try_from)
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for ContentSteering {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            server_uri: attributes
+                .get("SERVER-URI")
+                .ok_or(Error::HLSFormat("HLS missing SERVER-URI".to_string()))?
+                .clone(),
+            pathway_id: attributes.get("PATHWAY-ID").cloned(),
+        })
+    }
+}
+
+impl TryFrom<&String> for KeyMethod {
+    type Error = Error;
+
+    fn try_from(method: &String) -> Result<Self> {
+        match method.as_str() {
+            "NONE" => Ok(KeyMethod::None),
+            "AES-128" => Ok(KeyMethod::Aes128),
+            "SAMPLE-AES" => Ok(KeyMethod::SampleAes),
+            "SAMPLE-AES-CTR" => Ok(KeyMethod::SampleAesCtr),
+            _ => Err(Error::HLSFormat(format!(
+                "Invalid key method specified: {}",
+                method
+            ))),
+        }
+    }
+}
+
+impl TryFrom<&str> for InitializationVector {
+    type Error = Error;
+
+    fn try_from(iv: &str) -> Result<Self> {
+        let invalid = || Error::HLSFormat(format!("Invalid IV specified: {}", iv));
+        let hex = iv
+            .strip_prefix("0x")
+            .or_else(|| iv.strip_prefix("0X"))
+            .ok_or_else(invalid)?;
+        if hex.is_empty() || hex.len() > 32 {
+            return Err(invalid());
+        }
+        u128::from_str_radix(hex, 16)
+            .map(Self)
+            .map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for InitializationVector {
+    type Error = Error;
+
+    fn try_from(iv: String) -> Result<Self> {
+        iv.as_str().try_into()
+    }
+}
+
+impl From<InitializationVector> for String {
+    fn from(iv: InitializationVector) -> Self {
+        iv.to_string()
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for SessionKey {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        let key: Key = attributes.try_into()?;
+        if key.method == KeyMethod::None {
+            return Err(Error::HLSFormat(
+                "EXT-X-SESSION-KEY METHOD must not be NONE".to_string(),
+            ));
+        }
+        Ok(Self(key))
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Key {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        let method = attributes
+            .get("METHOD")
+            .ok_or(Error::HLSFormat("HLS missing METHOD".to_string()))?
+            .try_into()?;
+        let uri = attributes.get("URI").cloned();
+        if method != KeyMethod::None && uri.is_none() {
+            return Err(Error::HLSFormat("HLS missing URI".to_string()));
+        }
+
+        Ok(Self {
+            method,
+            uri,
+            iv: attributes
+                .get("IV")
+                .map(|iv| InitializationVector::try_from(iv.as_str()))
+                .transpose()?,
+            key_format: attributes.get("KEYFORMAT").cloned(),
+            key_format_versions: attributes
+                .get("KEYFORMATVERSIONS")
+                .map(|versions| {
+                    versions
+                        .split('/')
+                        .map(|version| version.parse())
+                        .collect::<std::result::Result<_, _>>()
+                })
+                .transpose()?,
         })
     }
 }
@@ -106,6 +499,15 @@ impl TryFrom<HashMap<String, String>> for IFrame {
     type Error = Error;
 
     fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Self::from_attributes(attributes, &ParseOptions::default())
+    }
+}
+
+impl IFrame {
+    fn from_attributes(
+        attributes: HashMap<String, String>,
+        options: &ParseOptions,
+    ) -> Result<Self> {
         Ok(Self {
             bandwidth: attributes
                 .get("BANDWIDTH")
@@ -113,22 +515,42 @@ impl TryFrom<HashMap<String, String>> for IFrame {
                 .parse()?,
             codecs: attributes
                 .get("CODECS")
-                .ok_or(Error::HLSFormat("HLS missing CODECS".to_string()))?
-                .clone(),
-            resolution: Resolution::try_from(
-                attributes
-                    .get("RESOLUTION")
-                    .ok_or(Error::HLSFormat("HLS missing RESOLUTION".to_string()))?
-                    .clone(),
-            )?,
+                .map(|codecs| Codecs::from(codecs.as_str())),
+            resolution: attributes
+                .get("RESOLUTION")
+                .cloned()
+                .map(Resolution::try_from)
+                .transpose()?,
             video_range: attributes
                 .get("VIDEO-RANGE")
-                .ok_or(Error::HLSFormat("HLS missing VIDEO-RANGE".to_string()))?
-                .try_into()?,
+                .map(|video_range| VideoRange::with_options(video_range, options))
+                .transpose()?,
+            hdcp_level: attributes
+                .get("HDCP-LEVEL")
+                .map(HdcpLevel::try_from)
+                .transpose()?,
+            video: attributes.get("VIDEO").cloned(),
+            score: attributes
+                .get("SCORE")
+                .map(|score| -> Result<Score> { score.parse::<f64>()?.try_into() })
+                .transpose()?,
+            supplemental_codecs: attributes
+                .get("SUPPLEMENTAL-CODECS")
+                .map(|codecs| Codecs::from(codecs.as_str())),
+            allowed_cpc: attributes
+                .get("ALLOWED-CPC")
+                .map(|allowed_cpc| allowed_cpc.split(',').map(AllowedCpc::try_from).collect())
+                .transpose()?,
+            stable_variant_id: attributes.get("STABLE-VARIANT-ID").cloned(),
+            pathway_id: attributes.get("PATHWAY-ID").cloned(),
+            req_video_layout: attributes
+                .get("REQ-VIDEO-LAYOUT")
+                .map(|layouts| layouts.split(',').map(VideoLayout::from).collect()),
             uri: attributes
                 .get("URI")
                 .ok_or(Error::HLSFormat("HLS missing URI".to_string()))?
                 .clone(),
+            line: SourceLine::default(),
         })
     }
 }
@@ -137,6 +559,15 @@ impl TryFrom<HashMap<String, String>> for VariantStream {
     type Error = Error;
 
     fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Self::from_attributes(attributes, &ParseOptions::default())
+    }
+}
+
+impl VariantStream {
+    fn from_attributes(
+        attributes: HashMap<String, String>,
+        options: &ParseOptions,
+    ) -> Result<Self> {
         Ok(Self {
             uri: attributes
                 .get("URI")
@@ -148,77 +579,627 @@ impl TryFrom<HashMap<String, String>> for VariantStream {
                 .parse()?,
             average_bandwidth: attributes
                 .get("AVERAGE-BANDWIDTH")
-                .ok_or(Error::HLSFormat(
-                    "HLS missing AVERAGE-BANDWIDTH".to_string(),
-                ))?
-                .parse()?,
+                .map(|average_bandwidth| average_bandwidth.parse())
+                .transpose()?,
             codecs: attributes
                 .get("CODECS")
-                .ok_or(Error::HLSFormat("HLS missing CODECS".to_string()))?
-                .clone(),
-            resolution: Resolution::try_from(
-                attributes
-                    .get("RESOLUTION")
-                    .ok_or(Error::HLSFormat("HLS missing RESOLUTION".to_string()))?
-                    .clone(),
-            )?,
+                .map(|codecs| Codecs::from(codecs.as_str())),
+            resolution: attributes
+                .get("RESOLUTION")
+                .cloned()
+                .map(Resolution::try_from)
+                .transpose()?,
             video_range: attributes
                 .get("VIDEO-RANGE")
-                .ok_or(Error::HLSFormat("HLS missing VIDEO-RANGE".to_string()))?
-                .try_into()?,
+                .map(|video_range| VideoRange::with_options(video_range, options))
+                .transpose()?,
             frame_rate: attributes
                 .get("FRAME-RATE")
-                .ok_or(Error::HLSFormat("HLS missing FRAME-RATE".to_string()))?
-                .clone(),
-            audio: attributes
-                .get("AUDIO")
-                .ok_or(Error::HLSFormat("HLS missing AUDIO".to_string()))?
-                .clone(),
-            closed_captions: attributes
-                .get("CLOSED-CAPTIONS")
-                .ok_or(Error::HLSFormat("HLS missing CLOSED-CAPTIONS".to_string()))?
-                .clone(),
+                .map(|frame_rate| -> Result<FrameRate> { frame_rate.parse::<f64>()?.try_into() })
+                .transpose()?,
+            audio: attributes.get("AUDIO").cloned(),
+            subtitles: attributes.get("SUBTITLES").cloned(),
+            closed_captions: attributes.get("CLOSED-CAPTIONS").cloned(),
+            hdcp_level: attributes
+                .get("HDCP-LEVEL")
+                .map(HdcpLevel::try_from)
+                .transpose()?,
+            video: attributes.get("VIDEO").cloned(),
+            score: attributes
+                .get("SCORE")
+                .map(|score| -> Result<Score> { score.parse::<f64>()?.try_into() })
+                .transpose()?,
+            supplemental_codecs: attributes
+                .get("SUPPLEMENTAL-CODECS")
+                .map(|codecs| Codecs::from(codecs.as_str())),
+            allowed_cpc: attributes
+                .get("ALLOWED-CPC")
+                .map(|allowed_cpc| allowed_cpc.split(',').map(AllowedCpc::try_from).collect())
+                .transpose()?,
+            stable_variant_id: attributes.get("STABLE-VARIANT-ID").cloned(),
+            pathway_id: attributes.get("PATHWAY-ID").cloned(),
+            req_video_layout: attributes
+                .get("REQ-VIDEO-LAYOUT")
+                .map(|layouts| layouts.split(',').map(VideoLayout::from).collect()),
+            line: SourceLine::default(),
         })
     }
 }
 
-impl TryFrom<Vec<(String, HashMap<String, String>)>> for MasterPlaylist {
+impl TryFrom<Vec<Tag>> for MasterPlaylist {
     type Error = Error;
 
-    fn try_from(tags: Vec<(String, HashMap<String, String>)>) -> Result<Self, Self::Error> {
+    fn try_from(tags: Vec<Tag>) -> Result<Self, Self::Error> {
+        Self::from_tags(tags, &ParseOptions::default())
+    }
+}
+
+impl MasterPlaylist {
+    pub(crate) fn from_tags(tags: Vec<Tag>, options: &ParseOptions) -> Result<Self> {
         let mut variant_streams = vec![];
         let mut basic_tags = vec![];
         let mut media_tags = vec![];
         let mut i_frames = vec![];
+        let mut version = None;
+        let mut session_data = vec![];
+        let mut session_keys = vec![];
+        let mut content_steering = None;
+        let mut variables = BTreeMap::new();
+        let mut query_param_variables = BTreeSet::new();
+        let mut unknown_tags = vec![];
+        let starts_with_extm3u = tags.first().map(|tag| tag.name.as_str()) == Some("EXTM3U");
 
-        for (name, attributes) in tags {
-            match name.as_str() {
-                "EXT-X-STREAM-INF" => variant_streams.push(VariantStream::try_from(attributes)?),
-                "EXT-X-MEDIA" => media_tags.push(MediaTag::try_from(attributes)?),
-                "EXT-X-I-FRAME-STREAM-INF" => i_frames.push(IFrame::try_from(attributes)?),
-                _ => {
-                    if attributes.len() > 0 {
+        for Tag {
+            name,
+            attributes,
+            raw_value,
+            location,
+            ..
+        } in tags
+        {
+            let convert_tag = || -> Result<()> {
+                match name.as_str() {
+                    "EXT-X-VERSION" => version = Some(tag_value(&name, &attributes)?.parse()?),
+                    "EXT-X-STREAM-INF" => variant_streams.push(VariantStream {
+                        line: SourceLine(location.line),
+                        ..VariantStream::from_attributes(attributes, options)?
+                    }),
+                    "EXT-X-MEDIA" => media_tags.push(MediaTag {
+                        line: SourceLine(location.line),
+                        ..MediaTag::try_from(attributes)?
+                    }),
+                    "EXT-X-I-FRAME-STREAM-INF" => i_frames.push(IFrame {
+                        line: SourceLine(location.line),
+                        ..IFrame::from_attributes(attributes, options)?
+                    }),
+                    "EXT-X-SESSION-DATA" => session_data.push(SessionData::try_from(attributes)?),
+                    "EXT-X-SESSION-KEY" => session_keys.push(SessionKey::try_from(attributes)?),
+                    "EXT-X-CONTENT-STEERING" => {
+                        content_steering = Some(ContentSteering::try_from(attributes)?)
+                    }
+                    "EXT-X-DEFINE" => {
+                        define(&attributes, &mut variables)?;
+                        if let Some(name) = attributes.get("QUERYPARAM") {
+                            query_param_variables.insert(name.clone());
+                        }
+                    }
+                    _ if MASTER_PLAYLIST_BASIC_TAGS.contains(&name.as_str()) => {
+                        basic_tags.push(name.clone())
+                    }
+                    _ if MEDIA_PLAYLIST_TAGS.contains(&name.as_str()) => {
                         return Err(Error::HLSFormat(format!(
-                            "Unknown tag with attributes found: {}",
+                            "{} is only allowed in media playlists",
                             name
-                        )));
+                        )))
                     }
-                    basic_tags.push(name);
+                    _ => unknown_tags.push(UnknownTag {
+                        name: name.clone(),
+                        value: raw_value,
+                        line: location.line,
+                    }),
                 }
-            }
+                Ok(())
+            };
+            convert_tag().map_err(|e| e.in_tag(&name, &location))?;
         }
 
         // Sort everything now while we've got mutable refs
-        variant_streams.sort();
-        i_frames.sort();
-        media_tags.sort();
-        basic_tags.sort();
+        if !options.preserve_order {
+            variant_streams.sort();
+            i_frames.sort();
+            media_tags.sort();
+            basic_tags.sort();
+        }
 
         Ok(Self {
+            version,
+            starts_with_extm3u,
             variant_streams,
             basic_tags,
             i_frames,
             media_tags,
+            session_data,
+            session_keys,
+            content_steering,
+            variables,
+            query_param_variables,
+            unknown_tags,
+        })
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for ServerControl {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            can_skip_until: attributes
+                .get("CAN-SKIP-UNTIL")
+                .map(|seconds| seconds.parse())
+                .transpose()?,
+            can_skip_dateranges: yes_no(&attributes, "CAN-SKIP-DATERANGES")?,
+            hold_back: attributes
+                .get("HOLD-BACK")
+                .map(|seconds| seconds.parse())
+                .transpose()?,
+            part_hold_back: attributes
+                .get("PART-HOLD-BACK")
+                .map(|seconds| seconds.parse())
+                .transpose()?,
+            can_block_reload: yes_no(&attributes, "CAN-BLOCK-RELOAD")?,
+        })
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Part {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            duration: attributes
+                .get("DURATION")
+                .ok_or(Error::HLSFormat("HLS missing DURATION".to_string()))?
+                .parse()?,
+            uri: attributes
+                .get("URI")
+                .ok_or(Error::HLSFormat("HLS missing URI".to_string()))?
+                .clone(),
+            independent: yes_no(&attributes, "INDEPENDENT")?,
+            byte_range: attributes
+                .get("BYTERANGE")
+                .map(ByteRange::try_from)
+                .transpose()?,
+            gap: yes_no(&attributes, "GAP")?,
+        })
+    }
+}
+
+impl TryFrom<&String> for PreloadHintType {
+    type Error = Error;
+
+    fn try_from(hint_type: &String) -> Result<Self> {
+        match hint_type.as_str() {
+            "PART" => Ok(PreloadHintType::Part),
+            "MAP" => Ok(PreloadHintType::Map),
+            _ => Err(Error::HLSFormat(format!(
+                "Invalid preload hint type specified: {}",
+                hint_type
+            ))),
+        }
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for PreloadHint {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            hint_type: attributes
+                .get("TYPE")
+                .ok_or(Error::HLSFormat("HLS missing TYPE".to_string()))?
+                .try_into()?,
+            uri: attributes
+                .get("URI")
+                .ok_or(Error::HLSFormat("HLS missing URI".to_string()))?
+                .clone(),
+            byte_range_start: attributes
+                .get("BYTERANGE-START")
+                .map(|start| start.parse())
+                .transpose()?,
+            byte_range_length: attributes
+                .get("BYTERANGE-LENGTH")
+                .map(|length| length.parse())
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for RenditionReport {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            uri: attributes
+                .get("URI")
+                .ok_or(Error::HLSFormat("HLS missing URI".to_string()))?
+                .clone(),
+            last_msn: attributes
+                .get("LAST-MSN")
+                .map(|msn| msn.parse())
+                .transpose()?,
+            last_part: attributes
+                .get("LAST-PART")
+                .map(|part| part.parse())
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Skip {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            skipped_segments: attributes
+                .get("SKIPPED-SEGMENTS")
+                .ok_or(Error::HLSFormat("HLS missing SKIPPED-SEGMENTS".to_string()))?
+                .parse()?,
+            recently_removed_dateranges: attributes
+                .get("RECENTLY-REMOVED-DATERANGES")
+                .map(|ids| ids.split('\t').map(str::to_string).collect()),
+        })
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for DateRange {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        let seconds = |name| {
+            attributes
+                .get(name)
+                .map(|seconds: &String| seconds.parse())
+                .transpose()
+        };
+        let splice_info_section = |name| {
+            attributes
+                .get(name)
+                .map(|section: &String| scte35(name, section))
+                .transpose()
+        };
+
+        Ok(Self {
+            id: attributes
+                .get("ID")
+                .ok_or(Error::HLSFormat("HLS missing ID".to_string()))?
+                .clone(),
+            class: attributes.get("CLASS").cloned(),
+            start_date: attributes.get("START-DATE").cloned(),
+            end_date: attributes.get("END-DATE").cloned(),
+            duration: seconds("DURATION")?,
+            planned_duration: seconds("PLANNED-DURATION")?,
+            end_on_next: yes_no(&attributes, "END-ON-NEXT")?,
+            scte35_cmd: splice_info_section("SCTE35-CMD")?,
+            scte35_out: splice_info_section("SCTE35-OUT")?,
+            scte35_in: splice_info_section("SCTE35-IN")?,
+            client_attributes: attributes
+                .iter()
+                .filter(|(name, _)| name.starts_with("X-"))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        })
+    }
+}
+
+/// Decodes the hexadecimal splice_info_section of a SCTE35-CMD, SCTE35-OUT or SCTE35-IN
+/// attribute.
+fn scte35(name: &str, section: &str) -> Result<SpliceInfoSection> {
+    let invalid = || Error::HLSFormat(format!("{} must be hexadecimal, found {}", name, section));
+    let hex = section
+        .strip_prefix("0x")
+        .or_else(|| section.strip_prefix("0X"))
+        .ok_or_else(invalid)?;
+    if hex.is_empty() || hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<std::result::Result<Vec<u8>, _>>()
+        .map_err(|_| invalid())?;
+    SpliceInfoSection::try_from(bytes.as_slice()).map_err(|e| match e {
+        Error::HLSFormat(message) => Error::HLSFormat(format!("Invalid {}: {}", name, message)),
+        other => other,
+    })
+}
+
+impl TryFrom<HashMap<String, String>> for Segment {
+    type Error = Error;
+
+    fn try_from(attributes: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            duration: attributes
+                .get("DURATION")
+                .ok_or(Error::HLSFormat("HLS missing EXTINF duration".to_string()))?
+                .parse()?,
+            title: attributes.get("TITLE").cloned(),
+            uri: attributes
+                .get("URI")
+                .ok_or(Error::HLSFormat("HLS missing segment URI".to_string()))?
+                .clone(),
+            keys: vec![],
+            map: None,
+            byte_range: None,
+            parts: vec![],
+        })
+    }
+}
+
+/// Works out the absolute `(start, length)` of a segment's sub-range. Without an offset the
+/// sub-range starts straight after the previous segment's, which must be in the same resource.
+fn resolve_byte_range(
+    byte_range: ByteRange,
+    uri: &str,
+    previous_range: &Option<(String, u64)>,
+) -> Result<(u64, u64)> {
+    let start = match (byte_range.offset, previous_range) {
+        (Some(offset), _) => offset,
+        (None, Some((previous_uri, end))) if previous_uri == uri => *end,
+        (None, Some((previous_uri, _))) => {
+            return Err(Error::HLSFormat(format!(
+                "EXT-X-BYTERANGE for {} has no offset but the previous sub-range is of {}",
+                uri, previous_uri
+            )))
+        }
+        (None, None) => {
+            return Err(Error::HLSFormat(format!(
+                "EXT-X-BYTERANGE for {} has no offset and there is no previous sub-range",
+                uri
+            )))
+        }
+    };
+    Ok((start, byte_range.length))
+}
+
+/// Records an EXT-X-DEFINE, which variable substitution has already resolved to a NAME and VALUE.
+fn define(
+    attributes: &HashMap<String, String>,
+    variables: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    let name = attributes
+        .get("NAME")
+        .ok_or(Error::HLSFormat("HLS missing NAME".to_string()))?;
+    let value = attributes
+        .get("VALUE")
+        .ok_or(Error::HLSFormat("HLS missing VALUE".to_string()))?;
+    variables.insert(name.clone(), value.clone());
+    Ok(())
+}
+
+/// Converts an enumerated YES/NO attribute, which is `None` if the attribute isn't present.
+fn yes_no(attributes: &HashMap<String, String>, name: &str) -> Result<Option<bool>> {
+    attributes
+        .get(name)
+        .map(|value| match value.as_str() {
+            "YES" => Ok(true),
+            "NO" => Ok(false),
+            _ => Err(Error::HLSFormat(format!(
+                "{} must be YES or NO, found {}",
+                name, value
+            ))),
         })
+        .transpose()
+}
+
+/// Returns the single value of a tag such as `#EXT-X-TARGETDURATION:10`.
+fn tag_value<'a>(name: &str, attributes: &'a HashMap<String, String>) -> Result<&'a String> {
+    attributes
+        .get("VALUE")
+        .ok_or(Error::HLSFormat(format!("HLS missing value for {}", name)))
+}
+
+/// Whether a media playlist tag has an enumerated value this parser doesn't know about, such as a
+/// new KEY METHOD. In lenient mode these tags are kept as unknown tags.
+fn has_unknown_value(name: &str, attributes: &HashMap<String, String>) -> bool {
+    match name {
+        "EXT-X-PLAYLIST-TYPE" => attributes
+            .get("VALUE")
+            .is_some_and(|value| PlaylistType::try_from(value).is_err()),
+        "EXT-X-KEY" => attributes
+            .get("METHOD")
+            .is_some_and(|method| KeyMethod::try_from(method).is_err()),
+        "EXT-X-PRELOAD-HINT" => attributes
+            .get("TYPE")
+            .is_some_and(|hint_type| PreloadHintType::try_from(hint_type).is_err()),
+        _ => false,
+    }
+}
+
+impl TryFrom<Vec<Tag>> for MediaPlaylist {
+    type Error = Error;
+
+    fn try_from(tags: Vec<Tag>) -> Result<Self, Self::Error> {
+        Self::from_tags(tags, &ParseOptions::default())
+    }
+}
+
+impl MediaPlaylist {
+    pub(crate) fn from_tags(tags: Vec<Tag>, options: &ParseOptions) -> Result<Self> {
+        let mut version = None;
+        let mut target_duration = None;
+        let mut media_sequence = 0;
+        let mut end_list = false;
+        let mut playlist_type = None;
+        let mut segments = vec![];
+        // The EXT-X-KEY and EXT-X-MAP tags apply to every segment after them until replaced
+        let mut keys: Vec<Key> = vec![];
+        let mut map = None;
+        let mut byte_range = None;
+        // The resource and end of the previous segment's sub-range, for implicit offsets
+        let mut previous_range: Option<(String, u64)> = None;
+        // Parts come before the EXTINF of the segment they make up
+        let mut parts = vec![];
+        let mut server_control = None;
+        let mut part_target = None;
+        let mut preload_hints = vec![];
+        let mut rendition_reports = vec![];
+        let mut skip = None;
+        let mut date_ranges = vec![];
+        let mut basic_tags = vec![];
+        let mut variables = BTreeMap::new();
+        let mut unknown_tags = vec![];
+
+        for Tag {
+            name,
+            attributes,
+            raw_value,
+            location,
+            ..
+        } in tags
+        {
+            let convert_tag = || -> Result<()> {
+                match name.as_str() {
+                    _ if options.lenient && has_unknown_value(&name, &attributes) => unknown_tags
+                        .push(UnknownTag {
+                            name: name.clone(),
+                            value: raw_value,
+                            line: location.line,
+                        }),
+                    "EXT-X-VERSION" => version = Some(tag_value(&name, &attributes)?.parse()?),
+                    "EXTINF" => {
+                        let segment = Segment::try_from(attributes)?;
+                        let range = byte_range
+                            .take()
+                            .map(|byte_range| {
+                                resolve_byte_range(byte_range, &segment.uri, &previous_range)
+                            })
+                            .transpose()?;
+                        previous_range =
+                            range.map(|(start, length)| (segment.uri.clone(), start + length));
+                        segments.push(Segment {
+                            keys: keys.clone(),
+                            map: map.clone(),
+                            byte_range: range,
+                            parts: std::mem::take(&mut parts),
+                            ..segment
+                        })
+                    }
+                    "EXT-X-PART" => parts.push(Part::try_from(attributes)?),
+                    "EXT-X-PART-INF" => {
+                        part_target = Some(
+                            attributes
+                                .get("PART-TARGET")
+                                .ok_or(Error::HLSFormat("HLS missing PART-TARGET".to_string()))?
+                                .parse()?,
+                        )
+                    }
+                    "EXT-X-SERVER-CONTROL" => {
+                        server_control = Some(ServerControl::try_from(attributes)?)
+                    }
+                    "EXT-X-PRELOAD-HINT" => preload_hints.push(PreloadHint::try_from(attributes)?),
+                    "EXT-X-RENDITION-REPORT" => {
+                        rendition_reports.push(RenditionReport::try_from(attributes)?)
+                    }
+                    "EXT-X-SKIP" => skip = Some(Skip::try_from(attributes)?),
+                    "EXT-X-DATERANGE" => date_ranges.push(DateRange::try_from(attributes)?),
+                    "EXT-X-BYTERANGE" => {
+                        byte_range = Some(ByteRange::try_from(tag_value(&name, &attributes)?)?)
+                    }
+                    "EXT-X-KEY" => {
+                        let key = Key::try_from(attributes)?;
+                        if key.method == KeyMethod::None {
+                            keys.clear();
+                        } else {
+                            // A key only replaces the key with the same KEYFORMAT, so segments
+                            // can carry keys for several DRM systems at once
+                            keys.retain(|existing| existing.key_format() != key.key_format());
+                            keys.push(key);
+                        }
+                    }
+                    "EXT-X-MAP" => map = Some(Map::try_from(attributes)?),
+                    "EXT-X-TARGETDURATION" => {
+                        target_duration = Some(tag_value(&name, &attributes)?.parse()?)
+                    }
+                    "EXT-X-MEDIA-SEQUENCE" => {
+                        media_sequence = tag_value(&name, &attributes)?.parse()?
+                    }
+                    "EXT-X-PLAYLIST-TYPE" => {
+                        playlist_type = Some(tag_value(&name, &attributes)?.try_into()?)
+                    }
+                    "EXT-X-ENDLIST" => end_list = true,
+                    "EXT-X-DEFINE" => define(&attributes, &mut variables)?,
+                    _ if attributes.is_empty() => basic_tags.push(name.clone()),
+                    _ => unknown_tags.push(UnknownTag {
+                        name: name.clone(),
+                        value: raw_value,
+                        line: location.line,
+                    }),
+                }
+                Ok(())
+            };
+            convert_tag().map_err(|e| e.in_tag(&name, &location))?;
+        }
+
+        // Segments are played in order so unlike the master playlist nothing is sorted here
+        Ok(Self {
+            version,
+            target_duration: target_duration.ok_or(Error::HLSFormat(
+                "HLS missing EXT-X-TARGETDURATION".to_string(),
+            ))?,
+            media_sequence,
+            end_list,
+            playlist_type,
+            segments,
+            server_control,
+            part_target,
+            parts,
+            preload_hints,
+            rendition_reports,
+            skip,
+            date_ranges,
+            basic_tags,
+            variables,
+            unknown_tags,
+        })
+    }
+}
+
+/// Tags without a value which are kept in `MasterPlaylist::basic_tags`. Any other valueless tag
+/// is unknown and keeps its position as an `UnknownTag`.
+const MASTER_PLAYLIST_BASIC_TAGS: [&str; 2] = ["EXTM3U", "EXT-X-INDEPENDENT-SEGMENTS"];
+
+/// Tags which may only appear in a media playlist.
+const MEDIA_PLAYLIST_TAGS: [&str; 8] = [
+    "EXTINF",
+    "EXT-X-TARGETDURATION",
+    "EXT-X-MEDIA-SEQUENCE",
+    "EXT-X-PLAYLIST-TYPE",
+    "EXT-X-ENDLIST",
+    "EXT-X-PART-INF",
+    "EXT-X-PART",
+    "EXT-X-SERVER-CONTROL",
+];
+
+impl TryFrom<Vec<Tag>> for Playlist {
+    type Error = Error;
+
+    fn try_from(tags: Vec<Tag>) -> Result<Self, Self::Error> {
+        Self::from_tags(tags, &ParseOptions::default())
+    }
+}
+
+impl Playlist {
+    /// Whether the tags are of a media playlist rather than a master playlist.
+    pub(crate) fn is_media_playlist(tags: &[Tag]) -> bool {
+        tags.iter()
+            .any(|tag| MEDIA_PLAYLIST_TAGS.contains(&tag.name.as_str()))
+    }
+
+    pub(crate) fn from_tags(tags: Vec<Tag>, options: &ParseOptions) -> Result<Self> {
+        if Self::is_media_playlist(&tags) {
+            Ok(Playlist::Media(MediaPlaylist::from_tags(tags, options)?))
+        } else {
+            Ok(Playlist::Master(MasterPlaylist::from_tags(tags, options)?))
+        }
     }
 }
@@ -7,13 +7,34 @@
 /// example playlist.
 /// VIDEO-RANGE could only be found in a [draft](https://tools.ietf.org/html/draft-pantos-hls-rfc8216bis-00)
 /// so that diverges from the original RFC slightly.
+/// With the `serde` feature enabled the models serialize using their Rust field names, e.g.
+/// `average_bandwidth`, and enumerated values keep their HLS spelling, e.g. `CLOSED-CAPTIONS`.
+/// These names are part of the public API and are listed in the README.
+mod codecs;
 mod conversions;
+mod scte35;
+mod serialization;
+mod sorting;
+mod steering;
 #[cfg(test)]
 mod tests;
+mod validation;
 
 use crate::errors::Error;
 
-use std::num::ParseIntError;
+pub use codecs::{
+    AacObjectType, Av1, Avc, AvcProfile, Codec, CodecFormat, Codecs, DolbyVision, Hevc,
+    HevcProfile, Level, Tier, Vp9,
+};
+pub use scte35::{BreakDuration, ComponentSplice, SpliceCommand, SpliceInfoSection, SpliceInsert};
+pub use sorting::{SortBy, SortKey, SortOrder};
+pub use steering::{PathwayClone, SteeringManifest, UriReplacement};
+pub use validation::{validate, validate_media_playlist, Diagnostic, Entry, Rule, Severity};
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::num::{ParseFloatError, ParseIntError};
+use std::ops::Deref;
 
 impl From<ParseIntError> for Error {
     fn from(err: ParseIntError) -> Self {
@@ -21,21 +42,158 @@ impl From<ParseIntError> for Error {
     }
 }
 
-#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Default)]
+impl From<ParseFloatError> for Error {
+    fn from(err: ParseFloatError) -> Self {
+        Error::HLSFormat(err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Controls how parsed tags are converted into playlist models.
+pub struct ParseOptions {
+    /// Keep variant streams, media tags, I-frames and basic tags in the order they appear in
+    /// the playlist rather than sorting them.
+    pub preserve_order: bool,
+    /// Keep enumerated values this parser doesn't know about, such as a new VIDEO-RANGE, rather
+    /// than failing the whole conversion. `validate` reports each one as a warning. Media playlist
+    /// tags with such a value, e.g. an EXT-X-KEY with a new METHOD, are kept as unknown tags and
+    /// `validate_media_playlist` reports those.
+    pub lenient: bool,
+    /// The variables of the master playlist, which EXT-X-DEFINE tags with IMPORT read from when
+    /// parsing one of its media playlists.
+    pub imported_variables: BTreeMap<String, String>,
+    /// The URL the playlist was loaded from, which EXT-X-DEFINE tags with QUERYPARAM read from.
+    pub url: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The optimal pixel resolution in width and height.
-struct Resolution {
-    width: usize,
-    height: usize,
+pub struct Resolution {
+    pub width: usize,
+    pub height: usize,
 }
 
-#[derive(Debug, PartialEq, Ord, PartialOrd, Eq)]
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "f64", try_from = "f64")
+)]
+/// A frame rate in frames per second, kept to three decimal places as the spec requires.
+/// Storing it as a whole number of millihertz means frame rates can be compared and sorted exactly.
+pub struct FrameRate(u32);
+
+impl FrameRate {
+    /// Creates a frame rate from thousandths of a frame per second, e.g. 23976 for 23.976.
+    pub fn from_millis(millis: u32) -> Self {
+        Self(millis)
+    }
+
+    /// The frame rate in thousandths of a frame per second.
+    pub fn millis(&self) -> u32 {
+        self.0
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        f64::from(self.0) / 1000.0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "f64", try_from = "f64")
+)]
+/// A stream's SCORE, a non-negative number where higher is better.
+/// Scores are compared with `f64::total_cmp` so that streams can be sorted by them.
+pub struct Score(f64);
+
+impl Score {
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING-KEBAB-CASE"))]
+/// The High-bandwidth Digital Content Protection a stream needs.
+pub enum HdcpLevel {
+    /// `TYPE-0`
+    #[cfg_attr(feature = "serde", serde(rename = "TYPE-0"))]
+    Type0,
+    /// `TYPE-1`
+    #[cfg_attr(feature = "serde", serde(rename = "TYPE-1"))]
+    Type1,
+    /// `NONE`, the stream can be played without HDCP.
+    None,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// One entry of ALLOWED-CPC, e.g. `com.example.drm1:SMART-TV/PC`.
+pub struct AllowedCpc {
+    /// The KEYFORMAT of the key system this entry applies to.
+    pub key_format: String,
+    /// The content protection configuration labels the key system allows.
+    pub cpc: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", from = "String")
+)]
+/// A video layout from REQ-VIDEO-LAYOUT.
+pub enum VideoLayout {
+    /// `CH-STEREO`, stereoscopic video with a view for each eye.
+    ChStereo,
+    /// `CH-MONO`, monoscopic video.
+    ChMono,
+    /// Any other layout, as written in the playlist.
+    Other(String),
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", from = "String")
+)]
 /// Related to the encoding of the video.
-enum VideoRange {
+pub enum VideoRange {
     PQ,
     SDR,
+    HLG,
+    /// A value this parser doesn't know about, only kept when parsing leniently.
+    Other(String),
 }
 
-#[derive(Debug, PartialEq, Ord, PartialOrd, Eq)]
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A variant stream is a set of renditions that can be combined to play a media presentation.
 pub struct VariantStream {
     /// A media playlist that carries a rendition of this variant stream.
@@ -43,35 +201,186 @@ pub struct VariantStream {
     /// The peak segment bit rate in bits per second.
     bandwidth: u32,
     /// The average segment bit rate in bits per second.
-    average_bandwidth: u32,
+    average_bandwidth: Option<u32>,
     /// A comma seperated list of formats.
-    codecs: String,
+    codecs: Option<Codecs>,
     /// The optimal pixel resolution to display this video.
-    resolution: Resolution,
-    /// Either PQ or SDR, this value is related to the encoding.
-    video_range: VideoRange,
-    // the trait `Ord` is not implemented for `f32`
-    // and in this case it's not useful to sort on since they are all the same
-    // so just using a String
+    resolution: Option<Resolution>,
+    /// PQ, SDR or HLG, this value is related to the encoding.
+    video_range: Option<VideoRange>,
     /// The maximum frame rate for all videos in this stream, rounded to three decimal places.
-    frame_rate: String,
+    frame_rate: Option<FrameRate>,
     /// This value, if specified, must match the GROUP-ID value for a media tag (`TYPE=AUDIO`).
     /// It indicates the set of audio renditions to use when playing this presentation.
-    audio: String,
+    audio: Option<String>,
+    /// This value, if specified, must match the GROUP-ID value for a media tag (`TYPE=SUBTITLES`).
+    /// It indicates the set of subtitle renditions to use when playing this presentation.
+    subtitles: Option<String>,
     /// This value, if specified, must match the GROUP-ID value for a media tag (`TYPE=CLOSED-CAPTIONS`).
     /// It specifies which captions can be used to play this presentation.
-    closed_captions: String,
+    closed_captions: Option<String>,
+    /// The HDCP level required to play this stream without output restrictions.
+    hdcp_level: Option<HdcpLevel>,
+    /// This value, if specified, must match the GROUP-ID value for a media tag (`TYPE=VIDEO`).
+    video: Option<String>,
+    /// How much the content provider prefers this stream over others, higher is better.
+    score: Option<Score>,
+    /// Extra formats, such as Dolby Vision enhancement layers, that some clients can decode.
+    supplemental_codecs: Option<Codecs>,
+    /// The content protection configurations each key system allows this stream to be played with.
+    allowed_cpc: Option<Vec<AllowedCpc>>,
+    /// Identifies this stream across master playlists, e.g. for content steering.
+    stable_variant_id: Option<String>,
+    /// The content steering pathway this stream belongs to.
+    pathway_id: Option<String>,
+    /// The video layouts needed to play this stream, such as stereoscopic video.
+    req_video_layout: Option<Vec<VideoLayout>>,
+    /// Where this was found in the source playlist, so that unknown tags can be written back
+    /// next to it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    line: SourceLine,
 }
 
-#[derive(Debug, PartialEq, Ord, PartialOrd, Eq)]
-enum MediaType {
+impl VariantStream {
+    /// The media playlist that carries a rendition of this variant stream.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// The peak segment bit rate in bits per second.
+    pub fn bandwidth(&self) -> u32 {
+        self.bandwidth
+    }
+
+    /// The average segment bit rate in bits per second.
+    pub fn average_bandwidth(&self) -> Option<u32> {
+        self.average_bandwidth
+    }
+
+    /// The formats used in this stream.
+    pub fn codecs(&self) -> Option<&Codecs> {
+        self.codecs.as_ref()
+    }
+
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.resolution
+    }
+
+    pub fn video_range(&self) -> Option<&VideoRange> {
+        self.video_range.as_ref()
+    }
+
+    /// The maximum frame rate, rounded to three decimal places.
+    pub fn frame_rate(&self) -> Option<FrameRate> {
+        self.frame_rate
+    }
+
+    /// The GROUP-ID of the audio renditions for this variant stream.
+    pub fn audio(&self) -> Option<&str> {
+        self.audio.as_deref()
+    }
+
+    /// The GROUP-ID of the subtitle renditions for this variant stream.
+    pub fn subtitles(&self) -> Option<&str> {
+        self.subtitles.as_deref()
+    }
+
+    /// The GROUP-ID of the closed captions for this variant stream, or `NONE`.
+    pub fn closed_captions(&self) -> Option<&str> {
+        self.closed_captions.as_deref()
+    }
+
+    pub fn hdcp_level(&self) -> Option<HdcpLevel> {
+        self.hdcp_level
+    }
+
+    /// The GROUP-ID of the video renditions for this stream.
+    pub fn video(&self) -> Option<&str> {
+        self.video.as_deref()
+    }
+
+    pub fn score(&self) -> Option<Score> {
+        self.score
+    }
+
+    pub fn supplemental_codecs(&self) -> Option<&Codecs> {
+        self.supplemental_codecs.as_ref()
+    }
+
+    pub fn allowed_cpc(&self) -> Option<&[AllowedCpc]> {
+        self.allowed_cpc.as_deref()
+    }
+
+    pub fn stable_variant_id(&self) -> Option<&str> {
+        self.stable_variant_id.as_deref()
+    }
+
+    pub fn pathway_id(&self) -> Option<&str> {
+        self.pathway_id.as_deref()
+    }
+
+    /// The content steering pathway this stream belongs to. Streams without a PATHWAY-ID are
+    /// on the default pathway, `.`.
+    pub fn pathway(&self) -> &str {
+        self.pathway_id().unwrap_or(".")
+    }
+
+    pub fn req_video_layout(&self) -> Option<&[VideoLayout]> {
+        self.req_video_layout.as_deref()
+    }
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING-KEBAB-CASE"))]
+/// The type of media a media tag refers to, serialized with its HLS name such as `CLOSED-CAPTIONS`.
+pub enum MediaType {
     Audio,
     Video,
     Subtitles,
     ClosedCaptions,
 }
 
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The slash separated CHANNELS parameters of an audio rendition, e.g. `16/JOC`.
+/// These are ordered by channel count first.
+pub struct Channels {
+    /// The maximum number of independent, simultaneous audio channels.
+    pub count: u32,
+    /// Audio coding identifiers for spatial audio, such as `JOC` for Dolby Atmos.
+    pub spatial_audio: Vec<String>,
+    /// Special usage identifiers such as `BINAURAL`, `IMMERSIVE` or `DOWNMIX`.
+    pub special_usage: Vec<String>,
+}
+
+impl Channels {
+    /// The rendition carries spatial audio, e.g. Dolby Atmos, or is marked as immersive.
+    pub fn is_immersive(&self) -> bool {
+        !self.spatial_audio.is_empty()
+            || self
+                .special_usage
+                .iter()
+                .any(|usage| usage == "IMMERSIVE" || usage == "BINAURAL")
+    }
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+/// Which captions in the video stream a closed captions rendition refers to.
+pub enum InstreamId {
+    /// A CEA-608 channel, CC1 to CC4.
+    Cc(u8),
+    /// A CEA-708 service, SERVICE1 to SERVICE63.
+    Service(u8),
+}
+
 #[derive(Debug, PartialEq, Ord, PartialOrd, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A media tag is used to relate media playlists that contain alternative Renditions of the same
 /// content.
 pub struct MediaTag {
@@ -82,41 +391,748 @@ pub struct MediaTag {
     /// A human readable description.
     name: String,
     /// The primary language used in this rendition.
-    language: String,
-    // I wanted to get these next two values, default and autoselect
-    // into bool but ran into compiler issues
-    // and apparently rust doesn't automagically convert YES/NO to bools.
+    language: Option<String>,
+    /// A language associated with this rendition, such as the written form of a spoken language.
+    assoc_language: Option<String>,
+    /// Identifies this rendition across master playlists, e.g. for content steering.
+    stable_rendition_id: Option<String>,
+    // These three are kept as Options so that an attribute which was left out isn't written
+    // back as NO, and so validation can tell the two apart.
     /// The client should play this rendition by default.
-    default: String,
+    default: Option<bool>,
     /// The client *may* play this rendition by default. Matches current playblack environment.
-    autoselect: String,
-    /// A backslash separated list of parameters. These parameters vary based on the TYPE of media.
-    channels: String,
+    autoselect: Option<bool>,
+    /// The rendition contains content which is considered essential to play, only for subtitles.
+    forced: Option<bool>,
+    /// The captions within the video stream, which is required for closed captions and must
+    /// not be present otherwise.
+    instream_id: Option<InstreamId>,
+    /// Uniform Type Identifiers describing the characteristics of this rendition, such as
+    /// `public.accessibility.describes-video`.
+    characteristics: Option<Vec<String>>,
+    /// The channel count and spatial audio parameters of an audio rendition.
+    channels: Option<Channels>,
     /// Uri that identifies the media playlist file.
     /// If the media_type is ClosedCaptions this must not be present.
-    uri: String,
+    uri: Option<String>,
+    /// Where this was found in the source playlist, so that unknown tags can be written back
+    /// next to it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    line: SourceLine,
+}
+
+impl MediaTag {
+    pub fn media_type(&self) -> MediaType {
+        self.media_type
+    }
+
+    /// The group this rendition belongs to.
+    pub fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    /// A human readable description of this rendition.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The primary language used in this rendition.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    /// The client should play this rendition by default. A missing DEFAULT means NO.
+    pub fn default(&self) -> bool {
+        self.default.unwrap_or(false)
+    }
+
+    /// The client may choose to play this rendition. A missing AUTOSELECT means NO.
+    pub fn autoselect(&self) -> bool {
+        self.autoselect.unwrap_or(false)
+    }
+
+    /// The subtitles must be shown. A missing FORCED means NO.
+    pub fn forced(&self) -> bool {
+        self.forced.unwrap_or(false)
+    }
+
+    /// A language associated with this rendition, in addition to its primary language.
+    pub fn assoc_language(&self) -> Option<&str> {
+        self.assoc_language.as_deref()
+    }
+
+    pub fn stable_rendition_id(&self) -> Option<&str> {
+        self.stable_rendition_id.as_deref()
+    }
+
+    pub fn instream_id(&self) -> Option<InstreamId> {
+        self.instream_id
+    }
+
+    /// The Uniform Type Identifiers describing this rendition.
+    pub fn characteristics(&self) -> Option<&[String]> {
+        self.characteristics.as_deref()
+    }
+
+    pub fn channels(&self) -> Option<&Channels> {
+        self.channels.as_ref()
+    }
+
+    /// The media playlist for this rendition.
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
 }
 
 #[derive(Debug, PartialEq, Ord, PartialOrd, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A media playlist containing the I-frames of a multimedia presentation.
 pub struct IFrame {
     /// The average segment bit rate in bits per second.
     bandwidth: u32,
     /// A comma seperated list of formats.
-    codecs: String,
+    codecs: Option<Codecs>,
     /// The optimal pixel resolution to display this I-frame.
-    resolution: Resolution,
-    /// Either PQ or SDR, this value is related to the encoding.
-    video_range: VideoRange,
+    resolution: Option<Resolution>,
+    /// PQ, SDR or HLG, this value is related to the encoding.
+    video_range: Option<VideoRange>,
+    /// The HDCP level required to play this stream without output restrictions.
+    hdcp_level: Option<HdcpLevel>,
+    /// This value, if specified, must match the GROUP-ID value for a media tag (`TYPE=VIDEO`).
+    video: Option<String>,
+    /// How much the content provider prefers this stream over others, higher is better.
+    score: Option<Score>,
+    /// Extra formats, such as Dolby Vision enhancement layers, that some clients can decode.
+    supplemental_codecs: Option<Codecs>,
+    /// The content protection configurations each key system allows this stream to be played with.
+    allowed_cpc: Option<Vec<AllowedCpc>>,
+    /// Identifies this stream across master playlists, e.g. for content steering.
+    stable_variant_id: Option<String>,
+    /// The content steering pathway this stream belongs to.
+    pathway_id: Option<String>,
+    /// The video layouts needed to play this stream, such as stereoscopic video.
+    req_video_layout: Option<Vec<VideoLayout>>,
     /// The uri identifying this I-frame's media playlist file.
     uri: String,
+    /// Where this was found in the source playlist, so that unknown tags can be written back
+    /// next to it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    line: SourceLine,
+}
+
+impl IFrame {
+    /// The average segment bit rate in bits per second.
+    pub fn bandwidth(&self) -> u32 {
+        self.bandwidth
+    }
+
+    /// The formats used in this stream.
+    pub fn codecs(&self) -> Option<&Codecs> {
+        self.codecs.as_ref()
+    }
+
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.resolution
+    }
+
+    pub fn video_range(&self) -> Option<&VideoRange> {
+        self.video_range.as_ref()
+    }
+
+    pub fn hdcp_level(&self) -> Option<HdcpLevel> {
+        self.hdcp_level
+    }
+
+    /// The GROUP-ID of the video renditions for this stream.
+    pub fn video(&self) -> Option<&str> {
+        self.video.as_deref()
+    }
+
+    pub fn score(&self) -> Option<Score> {
+        self.score
+    }
+
+    pub fn supplemental_codecs(&self) -> Option<&Codecs> {
+        self.supplemental_codecs.as_ref()
+    }
+
+    pub fn allowed_cpc(&self) -> Option<&[AllowedCpc]> {
+        self.allowed_cpc.as_deref()
+    }
+
+    pub fn stable_variant_id(&self) -> Option<&str> {
+        self.stable_variant_id.as_deref()
+    }
+
+    pub fn pathway_id(&self) -> Option<&str> {
+        self.pathway_id.as_deref()
+    }
+
+    pub fn req_video_layout(&self) -> Option<&[VideoLayout]> {
+        self.req_video_layout.as_deref()
+    }
+
+    /// The I-frame media playlist.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
+/// How the file at the URI of an EXT-X-SESSION-DATA tag is formatted.
+pub enum SessionDataFormat {
+    /// A JSON document, the default when FORMAT is left out.
+    Json,
+    /// Binary data, which the client treats as opaque.
+    Raw,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Arbitrary session data carried by the master playlist, from EXT-X-SESSION-DATA.
+pub struct SessionData {
+    /// Identifies this piece of data, usually in reverse DNS form such as `com.example.title`.
+    data_id: String,
+    /// The data itself. Exactly one of VALUE and URI must be present.
+    value: Option<String>,
+    /// A file containing the data, formatted as described by FORMAT.
+    uri: Option<String>,
+    /// The language VALUE is written in.
+    language: Option<String>,
+    format: Option<SessionDataFormat>,
+}
+
+impl SessionData {
+    pub fn data_id(&self) -> &str {
+        &self.data_id
+    }
+
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    /// How the file at the URI is formatted. A missing FORMAT means JSON.
+    pub fn format(&self) -> SessionDataFormat {
+        self.format.unwrap_or(SessionDataFormat::Json)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING-KEBAB-CASE"))]
+/// How media is encrypted.
+pub enum KeyMethod {
+    /// The media is not encrypted.
+    None,
+    /// Whole segments are encrypted with AES-128 in CBC mode.
+    #[cfg_attr(feature = "serde", serde(rename = "AES-128"))]
+    Aes128,
+    /// Media samples are encrypted with AES-128, in a way that depends on the media format.
+    SampleAes,
+    /// Media samples are encrypted with AES-128 in CTR mode, as used by common encryption.
+    SampleAesCtr,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+/// The 128-bit initialization vector of a key, written as a hexadecimal sequence such as
+/// `0x9c7db8778570d05c3177c349fd9236aa`.
+pub struct InitializationVector(pub u128);
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How to decrypt media segments, from EXT-X-KEY.
+pub struct Key {
+    method: KeyMethod,
+    /// Where to get the key. Required unless the method is NONE.
+    uri: Option<String>,
+    iv: Option<InitializationVector>,
+    /// How the key is represented in the file at the URI. A missing KEYFORMAT means `identity`.
+    key_format: Option<String>,
+    /// The versions of the KEYFORMAT the key is compatible with.
+    key_format_versions: Option<Vec<u32>>,
+}
+
+impl Key {
+    pub fn method(&self) -> KeyMethod {
+        self.method
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        self.uri.as_deref()
+    }
+
+    pub fn iv(&self) -> Option<InitializationVector> {
+        self.iv
+    }
+
+    /// How the key is represented in the file at the URI.
+    pub fn key_format(&self) -> &str {
+        self.key_format.as_deref().unwrap_or("identity")
+    }
+
+    pub fn key_format_versions(&self) -> Option<&[u32]> {
+        self.key_format_versions.as_deref()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+/// A key for encrypted media playlists that clients can load ahead of time, from
+/// EXT-X-SESSION-KEY. It has the same attributes as EXT-X-KEY.
+pub struct SessionKey(Key);
+
+impl Deref for SessionKey {
+    type Target = Key;
+
+    fn deref(&self) -> &Key {
+        &self.0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Where to get a steering manifest, from EXT-X-CONTENT-STEERING.
+pub struct ContentSteering {
+    /// The steering server, which responds with a `SteeringManifest`.
+    pub server_uri: String,
+    /// The pathway to play from until the first steering manifest has loaded.
+    pub pathway_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// The line an entry was found on in the source playlist, or 0 if it wasn't parsed. It takes no
+/// part in comparisons, so an entry equals the same entry parsed from anywhere else.
+pub(crate) struct SourceLine(pub(crate) usize);
+
+impl PartialEq for SourceLine {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for SourceLine {}
+
+impl PartialOrd for SourceLine {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SourceLine {
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A tag that this parser doesn't recognise, such as a vendor specific tag.
+/// These are kept as they were found so that they can be written back out unchanged.
+pub struct UnknownTag {
+    /// The tag name, without the leading `#`.
+    pub name: String,
+    /// The raw text following the colon, e.g. the unparsed attribute list. `None` if the tag
+    /// has no value.
+    pub value: Option<String>,
+    /// The line this tag was found on in the original playlist.
+    pub line: usize,
 }
 
 #[derive(Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A set of variant streams, each of which describes a different version of the same content.
 pub struct MasterPlaylist {
+    /// The compatibility version of the playlist, from EXT-X-VERSION.
+    pub version: Option<u64>,
+    /// The playlist began with the EXTM3U tag, as every playlist must.
+    pub starts_with_extm3u: bool,
     pub variant_streams: Vec<VariantStream>,
     pub media_tags: Vec<MediaTag>,
     pub i_frames: Vec<IFrame>,
     pub basic_tags: Vec<String>,
+    /// EXT-X-SESSION-DATA tags, in the order they were found.
+    pub session_data: Vec<SessionData>,
+    /// EXT-X-SESSION-KEY tags, in the order they were found.
+    pub session_keys: Vec<SessionKey>,
+    pub content_steering: Option<ContentSteering>,
+    /// Variables from EXT-X-DEFINE, which have already been substituted into the playlist.
+    /// Pass these as `ParseOptions::imported_variables` when parsing its media playlists.
+    pub variables: BTreeMap<String, String>,
+    /// The names of the variables defined with QUERYPARAM, whose values came from the playlist
+    /// URL. These are written back as QUERYPARAM rather than with their current value.
+    pub query_param_variables: BTreeSet<String>,
+    /// Unrecognised tags, in the order they were found.
+    pub unknown_tags: Vec<UnknownTag>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A sub-range of a resource, written `<length>[@<offset>]`.
+pub struct ByteRange {
+    /// The length of the range in bytes.
+    pub length: u64,
+    /// The start of the range in bytes from the beginning of the resource.
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The media initialization section segments need in order to be parsed, from EXT-X-MAP.
+pub struct Map {
+    uri: String,
+    /// The part of the resource at the URI holding the initialization section.
+    byte_range: Option<ByteRange>,
+}
+
+impl Map {
+    /// The resource holding the initialization section.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn byte_range(&self) -> Option<ByteRange> {
+        self.byte_range
+    }
+
+    /// The absolute `(start, length)` in bytes of the initialization section within the
+    /// resource. A BYTERANGE without an offset starts at the beginning of the resource.
+    pub fn range(&self) -> Option<(u64, u64)> {
+        self.byte_range
+            .map(|byte_range| (byte_range.offset.unwrap_or(0), byte_range.length))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// What the server of a media playlist supports, from EXT-X-SERVER-CONTROL.
+pub struct ServerControl {
+    /// How many seconds from the end of the playlist a client can ask for a delta update
+    /// that skips the segments before it.
+    pub can_skip_until: Option<f64>,
+    /// Delta updates can also skip EXT-X-DATERANGE tags.
+    pub can_skip_dateranges: Option<bool>,
+    /// The minimum distance in seconds from the end of the playlist to start playing from.
+    pub hold_back: Option<f64>,
+    /// The minimum distance in seconds from the end of the playlist to start playing from in
+    /// low-latency mode.
+    pub part_hold_back: Option<f64>,
+    /// The server holds playlist requests with `_HLS_msn` until that segment is available.
+    pub can_block_reload: Option<bool>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A partial segment, from EXT-X-PART, which low-latency clients can play before the whole
+/// segment is available.
+pub struct Part {
+    /// The duration of this part in seconds.
+    duration: f64,
+    uri: String,
+    /// The part starts with an independent frame.
+    independent: Option<bool>,
+    /// The part of the resource at the URI holding this part.
+    byte_range: Option<ByteRange>,
+    /// The part isn't available and shouldn't be loaded.
+    gap: Option<bool>,
+}
+
+impl Part {
+    /// The duration of this part in seconds.
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// The part starts with an independent frame. A missing INDEPENDENT means NO.
+    pub fn independent(&self) -> bool {
+        self.independent.unwrap_or(false)
+    }
+
+    pub fn byte_range(&self) -> Option<ByteRange> {
+        self.byte_range
+    }
+
+    /// The part isn't available. A missing GAP means NO.
+    pub fn gap(&self) -> bool {
+        self.gap.unwrap_or(false)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
+/// The kind of resource a preload hint is for.
+pub enum PreloadHintType {
+    Part,
+    Map,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A resource the client can request before it is needed, from EXT-X-PRELOAD-HINT.
+pub struct PreloadHint {
+    hint_type: PreloadHintType,
+    uri: String,
+    /// Where the hinted resource starts within the resource at the URI.
+    byte_range_start: Option<u64>,
+    /// The length of the hinted resource, if known.
+    byte_range_length: Option<u64>,
+}
+
+impl PreloadHint {
+    pub fn hint_type(&self) -> PreloadHintType {
+        self.hint_type
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Where the hinted resource starts. A missing BYTERANGE-START means 0.
+    pub fn byte_range_start(&self) -> u64 {
+        self.byte_range_start.unwrap_or(0)
+    }
+
+    pub fn byte_range_length(&self) -> Option<u64> {
+        self.byte_range_length
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The state of another rendition's media playlist, from EXT-X-RENDITION-REPORT.
+pub struct RenditionReport {
+    /// The media playlist of the rendition, relative to this playlist.
+    uri: String,
+    /// The media sequence number of the last segment in the rendition.
+    last_msn: Option<u64>,
+    /// The index of the last part of that segment.
+    last_part: Option<u64>,
+}
+
+impl RenditionReport {
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn last_msn(&self) -> Option<u64> {
+        self.last_msn
+    }
+
+    pub fn last_part(&self) -> Option<u64> {
+        self.last_part
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Marks a playlist as a delta update that leaves out its oldest segments, from EXT-X-SKIP.
+pub struct Skip {
+    /// How many segments were left out.
+    pub skipped_segments: u64,
+    /// The IDs of EXT-X-DATERANGE tags removed since the skipped segments were last sent.
+    pub recently_removed_dateranges: Option<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A range of time in the playlist with attributes of its own, such as an ad break, from
+/// EXT-X-DATERANGE.
+pub struct DateRange {
+    /// Unique within the playlist.
+    id: String,
+    /// Which set of attributes the range has, as defined by whoever set the class.
+    class: Option<String>,
+    /// When the range starts, as an ISO 8601 date.
+    start_date: Option<String>,
+    /// When the range ends, as an ISO 8601 date.
+    end_date: Option<String>,
+    /// The duration of the range in seconds.
+    duration: Option<f64>,
+    /// The expected duration of the range in seconds, when the actual duration isn't yet known.
+    planned_duration: Option<f64>,
+    /// The range ends where the next range of the same class starts.
+    end_on_next: Option<bool>,
+    scte35_cmd: Option<SpliceInfoSection>,
+    /// Splices out of the network feed, e.g. at the start of an ad break.
+    scte35_out: Option<SpliceInfoSection>,
+    /// Splices back into the network feed.
+    scte35_in: Option<SpliceInfoSection>,
+    /// Attributes prefixed with `X-`, with their values as they were written.
+    client_attributes: BTreeMap<String, String>,
+}
+
+impl DateRange {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    pub fn start_date(&self) -> Option<&str> {
+        self.start_date.as_deref()
+    }
+
+    pub fn end_date(&self) -> Option<&str> {
+        self.end_date.as_deref()
+    }
+
+    pub fn duration(&self) -> Option<f64> {
+        self.duration
+    }
+
+    pub fn planned_duration(&self) -> Option<f64> {
+        self.planned_duration
+    }
+
+    pub fn end_on_next(&self) -> bool {
+        self.end_on_next.unwrap_or(false)
+    }
+
+    pub fn scte35_cmd(&self) -> Option<&SpliceInfoSection> {
+        self.scte35_cmd.as_ref()
+    }
+
+    pub fn scte35_out(&self) -> Option<&SpliceInfoSection> {
+        self.scte35_out.as_ref()
+    }
+
+    pub fn scte35_in(&self) -> Option<&SpliceInfoSection> {
+        self.scte35_in.as_ref()
+    }
+
+    pub fn client_attributes(&self) -> &BTreeMap<String, String> {
+        &self.client_attributes
+    }
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A media segment, specified by a URI and the EXTINF tag that precedes it.
+pub struct Segment {
+    /// The duration of this segment in seconds.
+    duration: f64,
+    /// An optional human readable title for this segment.
+    title: Option<String>,
+    /// The uri identifying this segment's media file.
+    uri: String,
+    /// The keys from the EXT-X-KEY tags in effect for this segment, one per KEYFORMAT.
+    keys: Vec<Key>,
+    /// The initialization section from the EXT-X-MAP tag in effect for this segment.
+    map: Option<Map>,
+    /// The absolute `(start, length)` in bytes of this segment within its resource, from
+    /// EXT-X-BYTERANGE.
+    byte_range: Option<(u64, u64)>,
+    /// The partial segments making up this segment, from the EXT-X-PART tags before it.
+    parts: Vec<Part>,
+}
+
+impl Segment {
+    /// The duration of this segment in seconds.
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// The media file for this segment.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// The keys this segment is encrypted with, one for each KEYFORMAT. Empty if the segment
+    /// isn't encrypted.
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+
+    pub fn map(&self) -> Option<&Map> {
+        self.map.as_ref()
+    }
+
+    /// The absolute `(start, length)` in bytes of this segment within the resource at its URI,
+    /// or `None` if the segment is the whole resource.
+    pub fn byte_range(&self) -> Option<(u64, u64)> {
+        self.byte_range
+    }
+
+    /// The partial segments that make up this segment in a low-latency playlist.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+}
+
+#[derive(Debug, PartialEq, Ord, PartialOrd, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
+/// Mutability information about a media playlist.
+pub enum PlaylistType {
+    /// Segments may only be appended to the end of the playlist.
+    Event,
+    /// The playlist cannot change.
+    Vod,
+}
+
+#[derive(Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A list of media segments that, when played sequentially, play the multimedia presentation.
+pub struct MediaPlaylist {
+    /// The compatibility version of the playlist, from EXT-X-VERSION.
+    pub version: Option<u64>,
+    /// The maximum segment duration in seconds.
+    pub target_duration: u64,
+    /// The media sequence number of the first segment.
+    pub media_sequence: u64,
+    /// No more segments will be added to this playlist.
+    pub end_list: bool,
+    pub playlist_type: Option<PlaylistType>,
+    pub segments: Vec<Segment>,
+    pub server_control: Option<ServerControl>,
+    /// The maximum duration of a partial segment in seconds, from EXT-X-PART-INF.
+    pub part_target: Option<f64>,
+    /// The parts of the segment still being produced, which come after the last full segment.
+    pub parts: Vec<Part>,
+    pub preload_hints: Vec<PreloadHint>,
+    pub rendition_reports: Vec<RenditionReport>,
+    /// Present when this playlist is a delta update.
+    pub skip: Option<Skip>,
+    pub date_ranges: Vec<DateRange>,
+    pub basic_tags: Vec<String>,
+    /// Variables from EXT-X-DEFINE, which have already been substituted into the playlist.
+    pub variables: BTreeMap<String, String>,
+    /// Unrecognised tags with values, in the order they were found.
+    pub unknown_tags: Vec<UnknownTag>,
+}
+
+#[derive(Debug, PartialEq)]
+/// Either kind of playlist, as determined by the tags it contains.
+pub enum Playlist {
+    Master(MasterPlaylist),
+    Media(MediaPlaylist),
 }
@@ -0,0 +1,227 @@
+use crate::errors::{Error, Result};
+use std::convert::TryFrom;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A SCTE-35 splice_info_section, as carried by the SCTE35-CMD, SCTE35-OUT and SCTE35-IN
+/// attributes of EXT-X-DATERANGE. Times are in ticks of the 90kHz MPEG-2 clock.
+pub struct SpliceInfoSection {
+    /// Added to every PTS time in the command.
+    pub pts_adjustment: u64,
+    /// Which tier of authorization the message is for, 0xFFF meaning all of them.
+    pub tier: u16,
+    pub command: SpliceCommand,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpliceCommand {
+    /// splice_null, which carries no command.
+    Null,
+    /// splice_insert, which signals a splice point such as the start or end of an ad break.
+    Insert(SpliceInsert),
+    /// time_signal, a splice time usually described further by segmentation descriptors.
+    TimeSignal {
+        /// `None` if the time isn't specified.
+        pts_time: Option<u64>,
+    },
+    /// Any other command, by its splice_command_type.
+    Other(u8),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpliceInsert {
+    pub event_id: u32,
+    /// A previously sent event with this ID has been cancelled. None of the other fields are
+    /// sent for a cancellation.
+    pub cancel: bool,
+    /// Splicing out of the network feed, e.g. into an ad break, rather than back into it.
+    pub out_of_network: bool,
+    /// Splice at the nearest opportunity rather than at a given time.
+    pub splice_immediate: bool,
+    /// When to splice the whole program. `None` if the splice is immediate, or is done per
+    /// component.
+    pub pts_time: Option<u64>,
+    /// When to splice each component, if the program isn't spliced as a whole.
+    pub components: Vec<ComponentSplice>,
+    pub break_duration: Option<BreakDuration>,
+    pub unique_program_id: u16,
+    /// Which avail within the break this is, starting from 1.
+    pub avail_num: u8,
+    pub avails_expected: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentSplice {
+    pub component_tag: u8,
+    pub pts_time: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreakDuration {
+    /// The splice back into the network feed happens automatically when the break ends.
+    pub auto_return: bool,
+    /// The length of the break in 90kHz ticks.
+    pub duration: u64,
+}
+
+/// Reads big-endian fields of any width from a byte slice.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    /// The position of the next bit to read.
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn read(&mut self, bits: usize) -> Result<u64> {
+        if self.position + bits > self.bytes.len() * 8 {
+            return Err(Error::HLSFormat(
+                "SCTE-35 splice_info_section is truncated".to_string(),
+            ));
+        }
+        let mut value = 0;
+        for _ in 0..bits {
+            let byte = self.bytes[self.position / 8];
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = value << 1 | u64::from(bit);
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn flag(&mut self) -> Result<bool> {
+        Ok(self.read(1)? == 1)
+    }
+
+    fn skip(&mut self, bits: usize) -> Result<()> {
+        self.read(bits).map(|_| ())
+    }
+}
+
+impl TryFrom<&[u8]> for SpliceInfoSection {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        let mut reader = BitReader::new(bytes);
+        let table_id = reader.read(8)?;
+        if table_id != 0xFC {
+            return Err(Error::HLSFormat(format!(
+                "SCTE-35 table_id must be 0xFC, found {:#04X}",
+                table_id
+            )));
+        }
+        // section_syntax_indicator, private_indicator, sap_type, section_length and
+        // protocol_version
+        reader.skip(24)?;
+        if reader.flag()? {
+            return Err(Error::HLSFormat(
+                "Encrypted SCTE-35 splice_info_sections aren't supported".to_string(),
+            ));
+        }
+        // encryption_algorithm
+        reader.skip(6)?;
+        let pts_adjustment = reader.read(33)?;
+        // cw_index
+        reader.skip(8)?;
+        let tier = reader.read(12)? as u16;
+        // splice_command_length, which may be 0xFFF for legacy commands, so commands are read
+        // by their structure instead
+        reader.skip(12)?;
+        let command = match reader.read(8)? {
+            0x00 => SpliceCommand::Null,
+            0x05 => SpliceCommand::Insert(splice_insert(&mut reader)?),
+            0x06 => SpliceCommand::TimeSignal {
+                pts_time: splice_time(&mut reader)?,
+            },
+            other => SpliceCommand::Other(other as u8),
+        };
+
+        Ok(Self {
+            pts_adjustment,
+            tier,
+            command,
+        })
+    }
+}
+
+fn splice_insert(reader: &mut BitReader) -> Result<SpliceInsert> {
+    let event_id = reader.read(32)? as u32;
+    let cancel = reader.flag()?;
+    reader.skip(7)?;
+    if cancel {
+        return Ok(SpliceInsert {
+            event_id,
+            cancel,
+            ..SpliceInsert::default()
+        });
+    }
+
+    let out_of_network = reader.flag()?;
+    let program_splice = reader.flag()?;
+    let has_duration = reader.flag()?;
+    let splice_immediate = reader.flag()?;
+    reader.skip(4)?;
+
+    let mut pts_time = None;
+    let mut components = vec![];
+    if program_splice {
+        if !splice_immediate {
+            pts_time = splice_time(reader)?;
+        }
+    } else {
+        for _ in 0..reader.read(8)? {
+            let component_tag = reader.read(8)? as u8;
+            let pts_time = if splice_immediate {
+                None
+            } else {
+                splice_time(reader)?
+            };
+            components.push(ComponentSplice {
+                component_tag,
+                pts_time,
+            });
+        }
+    }
+
+    let break_duration = if has_duration {
+        let auto_return = reader.flag()?;
+        reader.skip(6)?;
+        Some(BreakDuration {
+            auto_return,
+            duration: reader.read(33)?,
+        })
+    } else {
+        None
+    };
+
+    Ok(SpliceInsert {
+        event_id,
+        cancel,
+        out_of_network,
+        splice_immediate,
+        pts_time,
+        components,
+        break_duration,
+        unique_program_id: reader.read(16)? as u16,
+        avail_num: reader.read(8)? as u8,
+        avails_expected: reader.read(8)? as u8,
+    })
+}
+
+/// Reads a splice_time, which is `None` if its time_specified_flag isn't set.
+fn splice_time(reader: &mut BitReader) -> Result<Option<u64>> {
+    if reader.flag()? {
+        reader.skip(6)?;
+        Ok(Some(reader.read(33)?))
+    } else {
+        reader.skip(7)?;
+        Ok(None)
+    }
+}
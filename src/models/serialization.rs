@@ -0,0 +1,440 @@
+use crate::models::{
+    AllowedCpc, ByteRange, Channels, ContentSteering, FrameRate, HdcpLevel, IFrame,
+    InitializationVector, InstreamId, Key, KeyMethod, Map, MasterPlaylist, MediaTag, MediaType,
+    Resolution, Score, SessionData, SessionDataFormat, SessionKey, UnknownTag, VariantStream,
+    VideoLayout, VideoRange,
+};
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// Writes a comma separated attribute list, taking care of quoting and separators.
+struct AttributeWriter<'a, 'b> {
+    f: &'a mut Formatter<'b>,
+    first: bool,
+}
+
+impl<'a, 'b> AttributeWriter<'a, 'b> {
+    /// Starts an attribute list for the given tag name.
+    fn new(f: &'a mut Formatter<'b>, tag: &str) -> Result<Self, fmt::Error> {
+        write!(f, "#{}:", tag)?;
+        Ok(Self { f, first: true })
+    }
+
+    fn separator(&mut self) -> fmt::Result {
+        if !self.first {
+            write!(self.f, ",")?;
+        }
+        self.first = false;
+        Ok(())
+    }
+
+    /// Writes an attribute whose value is a decimal, enumerated string or resolution.
+    fn unquoted(&mut self, name: &str, value: impl Display) -> fmt::Result {
+        self.separator()?;
+        write!(self.f, "{}={}", name, value)
+    }
+
+    /// Writes an attribute whose value is a quoted string.
+    fn quoted(&mut self, name: &str, value: impl Display) -> fmt::Result {
+        self.separator()?;
+        write!(self.f, "{}=\"{}\"", name, value)
+    }
+
+    /// Writes an unquoted attribute only if it has a value.
+    fn optional_unquoted(&mut self, name: &str, value: Option<impl Display>) -> fmt::Result {
+        match value {
+            Some(value) => self.unquoted(name, value),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes a quoted attribute only if it has a value.
+    fn optional_quoted(&mut self, name: &str, value: Option<impl Display>) -> fmt::Result {
+        match value {
+            Some(value) => self.quoted(name, value),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Resolution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Joins a list into the comma separated form used by quoted attributes such as CHARACTERISTICS.
+fn comma_separated(items: &[impl Display]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for HdcpLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HdcpLevel::Type0 => write!(f, "TYPE-0"),
+            HdcpLevel::Type1 => write!(f, "TYPE-1"),
+            HdcpLevel::None => write!(f, "NONE"),
+        }
+    }
+}
+
+impl Display for AllowedCpc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.key_format, self.cpc.join("/"))
+    }
+}
+
+impl Display for VideoLayout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VideoLayout::ChStereo => write!(f, "CH-STEREO"),
+            VideoLayout::ChMono => write!(f, "CH-MONO"),
+            VideoLayout::Other(layout) => write!(f, "{}", layout),
+        }
+    }
+}
+
+/// Writes a boolean as the YES/NO enumerated string HLS uses.
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "YES"
+    } else {
+        "NO"
+    }
+}
+
+impl Display for Channels {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.count)?;
+        if !self.spatial_audio.is_empty() {
+            write!(f, "/{}", self.spatial_audio.join(","))?;
+        } else if !self.special_usage.is_empty() {
+            write!(f, "/-")?;
+        }
+        if !self.special_usage.is_empty() {
+            write!(f, "/{}", self.special_usage.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for InstreamId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InstreamId::Cc(channel) => write!(f, "CC{}", channel),
+            InstreamId::Service(service) => write!(f, "SERVICE{}", service),
+        }
+    }
+}
+
+impl Display for FrameRate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:03}", self.0 / 1000, self.0 % 1000)
+    }
+}
+
+impl Display for VideoRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VideoRange::PQ => write!(f, "PQ"),
+            VideoRange::SDR => write!(f, "SDR"),
+            VideoRange::HLG => write!(f, "HLG"),
+            VideoRange::Other(video_range) => write!(f, "{}", video_range),
+        }
+    }
+}
+
+impl Display for MediaType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaType::Audio => write!(f, "AUDIO"),
+            MediaType::Video => write!(f, "VIDEO"),
+            MediaType::Subtitles => write!(f, "SUBTITLES"),
+            MediaType::ClosedCaptions => write!(f, "CLOSED-CAPTIONS"),
+        }
+    }
+}
+
+impl Display for VariantStream {
+    /// Writes the EXT-X-STREAM-INF tag followed by the URI line it applies to.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut attrs = AttributeWriter::new(f, "EXT-X-STREAM-INF")?;
+        attrs.unquoted("BANDWIDTH", self.bandwidth)?;
+        attrs.optional_unquoted("AVERAGE-BANDWIDTH", self.average_bandwidth)?;
+        attrs.optional_quoted("CODECS", self.codecs.as_ref())?;
+        attrs.optional_unquoted("RESOLUTION", self.resolution)?;
+        attrs.optional_unquoted("FRAME-RATE", self.frame_rate)?;
+        attrs.optional_unquoted("VIDEO-RANGE", self.video_range.as_ref())?;
+        attrs.optional_quoted("AUDIO", self.audio.as_ref())?;
+        attrs.optional_quoted("SUBTITLES", self.subtitles.as_ref())?;
+        // CLOSED-CAPTIONS is either a quoted GROUP-ID or the enumerated string NONE
+        match &self.closed_captions {
+            Some(closed_captions) if closed_captions == "NONE" => {
+                attrs.unquoted("CLOSED-CAPTIONS", closed_captions)?
+            }
+            closed_captions => {
+                attrs.optional_quoted("CLOSED-CAPTIONS", closed_captions.as_ref())?
+            }
+        }
+        attrs.optional_unquoted("HDCP-LEVEL", self.hdcp_level)?;
+        attrs.optional_quoted("VIDEO", self.video.as_ref())?;
+        attrs.optional_unquoted("SCORE", self.score)?;
+        attrs.optional_quoted("SUPPLEMENTAL-CODECS", self.supplemental_codecs.as_ref())?;
+        attrs.optional_quoted(
+            "ALLOWED-CPC",
+            self.allowed_cpc.as_deref().map(comma_separated),
+        )?;
+        attrs.optional_quoted("STABLE-VARIANT-ID", self.stable_variant_id.as_ref())?;
+        attrs.optional_quoted("PATHWAY-ID", self.pathway_id.as_ref())?;
+        attrs.optional_quoted(
+            "REQ-VIDEO-LAYOUT",
+            self.req_video_layout.as_deref().map(comma_separated),
+        )?;
+        write!(f, "\n{}", self.uri)
+    }
+}
+
+impl Display for MediaTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut attrs = AttributeWriter::new(f, "EXT-X-MEDIA")?;
+        attrs.unquoted("TYPE", self.media_type)?;
+        attrs.quoted("GROUP-ID", &self.group_id)?;
+        attrs.quoted("NAME", &self.name)?;
+        attrs.optional_quoted("LANGUAGE", self.language.as_ref())?;
+        attrs.optional_quoted("ASSOC-LANGUAGE", self.assoc_language.as_ref())?;
+        attrs.optional_quoted("STABLE-RENDITION-ID", self.stable_rendition_id.as_ref())?;
+        attrs.optional_unquoted("DEFAULT", self.default.map(yes_no))?;
+        attrs.optional_unquoted("AUTOSELECT", self.autoselect.map(yes_no))?;
+        attrs.optional_unquoted("FORCED", self.forced.map(yes_no))?;
+        attrs.optional_quoted("INSTREAM-ID", self.instream_id)?;
+        attrs.optional_quoted(
+            "CHARACTERISTICS",
+            self.characteristics.as_deref().map(comma_separated),
+        )?;
+        attrs.optional_quoted("CHANNELS", self.channels.as_ref())?;
+        attrs.optional_quoted("URI", self.uri.as_ref())
+    }
+}
+
+impl Display for IFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut attrs = AttributeWriter::new(f, "EXT-X-I-FRAME-STREAM-INF")?;
+        attrs.unquoted("BANDWIDTH", self.bandwidth)?;
+        attrs.optional_quoted("CODECS", self.codecs.as_ref())?;
+        attrs.optional_unquoted("RESOLUTION", self.resolution)?;
+        attrs.optional_unquoted("VIDEO-RANGE", self.video_range.as_ref())?;
+        attrs.optional_unquoted("HDCP-LEVEL", self.hdcp_level)?;
+        attrs.optional_quoted("VIDEO", self.video.as_ref())?;
+        attrs.optional_unquoted("SCORE", self.score)?;
+        attrs.optional_quoted("SUPPLEMENTAL-CODECS", self.supplemental_codecs.as_ref())?;
+        attrs.optional_quoted(
+            "ALLOWED-CPC",
+            self.allowed_cpc.as_deref().map(comma_separated),
+        )?;
+        attrs.optional_quoted("STABLE-VARIANT-ID", self.stable_variant_id.as_ref())?;
+        attrs.optional_quoted("PATHWAY-ID", self.pathway_id.as_ref())?;
+        attrs.optional_quoted(
+            "REQ-VIDEO-LAYOUT",
+            self.req_video_layout.as_deref().map(comma_separated),
+        )?;
+        attrs.quoted("URI", &self.uri)
+    }
+}
+
+impl Display for SessionDataFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionDataFormat::Json => write!(f, "JSON"),
+            SessionDataFormat::Raw => write!(f, "RAW"),
+        }
+    }
+}
+
+impl Display for SessionData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut attrs = AttributeWriter::new(f, "EXT-X-SESSION-DATA")?;
+        attrs.quoted("DATA-ID", &self.data_id)?;
+        attrs.optional_quoted("VALUE", self.value.as_ref())?;
+        attrs.optional_quoted("URI", self.uri.as_ref())?;
+        attrs.optional_unquoted("FORMAT", self.format)?;
+        attrs.optional_quoted("LANGUAGE", self.language.as_ref())
+    }
+}
+
+impl Display for KeyMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyMethod::None => write!(f, "NONE"),
+            KeyMethod::Aes128 => write!(f, "AES-128"),
+            KeyMethod::SampleAes => write!(f, "SAMPLE-AES"),
+            KeyMethod::SampleAesCtr => write!(f, "SAMPLE-AES-CTR"),
+        }
+    }
+}
+
+impl Display for InitializationVector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:032x}", self.0)
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_tag(f, "EXT-X-KEY")
+    }
+}
+
+impl Display for SessionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_tag(f, "EXT-X-SESSION-KEY")
+    }
+}
+
+impl Key {
+    /// Writes the key's attributes under the given tag, as EXT-X-KEY and EXT-X-SESSION-KEY
+    /// share them.
+    fn write_tag(&self, f: &mut Formatter<'_>, tag: &str) -> fmt::Result {
+        let mut attrs = AttributeWriter::new(f, tag)?;
+        attrs.unquoted("METHOD", self.method)?;
+        attrs.optional_quoted("URI", self.uri.as_ref())?;
+        attrs.optional_unquoted("IV", self.iv)?;
+        attrs.optional_quoted("KEYFORMAT", self.key_format.as_ref())?;
+        attrs.optional_quoted(
+            "KEYFORMATVERSIONS",
+            self.key_format_versions.as_ref().map(|versions| {
+                versions
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            }),
+        )
+    }
+}
+
+impl Display for ContentSteering {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut attrs = AttributeWriter::new(f, "EXT-X-CONTENT-STEERING")?;
+        attrs.quoted("SERVER-URI", &self.server_uri)?;
+        attrs.optional_quoted("PATHWAY-ID", self.pathway_id.as_ref())
+    }
+}
+
+impl Display for ByteRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.length)?;
+        if let Some(offset) = self.offset {
+            write!(f, "@{}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut attrs = AttributeWriter::new(f, "EXT-X-MAP")?;
+        attrs.quoted("URI", &self.uri)?;
+        attrs.optional_quoted("BYTERANGE", self.byte_range)
+    }
+}
+
+impl Display for UnknownTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "#{}:{}", self.name, value),
+            None => write!(f, "#{}", self.name),
+        }
+    }
+}
+
+impl Display for MasterPlaylist {
+    /// Writes the playlist as m3u8 text. EXTM3U always comes first as the spec requires,
+    /// followed by the remaining tags grouped by kind. Each unknown tag is written straight after
+    /// the media tag, variant stream or I-frame it followed in the source playlist, or after the
+    /// other playlist tags if it came before all of them.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let entries: Vec<(usize, &dyn Display)> = self
+            .media_tags
+            .iter()
+            .map(|media_tag| (media_tag.line.0, media_tag as &dyn Display))
+            .chain(
+                self.variant_streams
+                    .iter()
+                    .map(|variant_stream| (variant_stream.line.0, variant_stream as &dyn Display)),
+            )
+            .chain(
+                self.i_frames
+                    .iter()
+                    .map(|i_frame| (i_frame.line.0, i_frame as &dyn Display)),
+            )
+            .collect();
+        // The unknown tags following each entry, and those which came before all of them.
+        // Entries that weren't parsed have no line, so nothing follows them
+        let mut entry_lines: Vec<(usize, usize)> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (line, _))| *line != 0)
+            .map(|(index, (line, _))| (*line, index))
+            .collect();
+        entry_lines.sort_unstable();
+        let mut leading_unknown_tags = vec![];
+        let mut unknown_tags_after = vec![vec![]; entries.len()];
+        for unknown_tag in &self.unknown_tags {
+            match entry_lines.partition_point(|(line, _)| *line < unknown_tag.line) {
+                0 => leading_unknown_tags.push(unknown_tag),
+                following => unknown_tags_after[entry_lines[following - 1].1].push(unknown_tag),
+            }
+        }
+
+        writeln!(f, "#EXTM3U")?;
+        if let Some(version) = self.version {
+            writeln!(f, "#EXT-X-VERSION:{}", version)?;
+        }
+        for basic_tag in self.basic_tags.iter().filter(|tag| *tag != "EXTM3U") {
+            writeln!(f, "#{}", basic_tag)?;
+        }
+        for (name, value) in &self.variables {
+            if self.query_param_variables.contains(name) {
+                writeln!(f, "#EXT-X-DEFINE:QUERYPARAM=\"{}\"", name)?;
+            } else {
+                writeln!(f, "#EXT-X-DEFINE:NAME=\"{}\",VALUE=\"{}\"", name, value)?;
+            }
+        }
+        for session_data in &self.session_data {
+            writeln!(f, "{}", session_data)?;
+        }
+        for session_key in &self.session_keys {
+            writeln!(f, "{}", session_key)?;
+        }
+        if let Some(content_steering) = &self.content_steering {
+            writeln!(f, "{}", content_steering)?;
+        }
+        for unknown_tag in leading_unknown_tags {
+            writeln!(f, "{}", unknown_tag)?;
+        }
+        for ((_, entry), unknown_tags) in entries.iter().zip(unknown_tags_after) {
+            writeln!(f, "{}", entry)?;
+            for unknown_tag in unknown_tags {
+                writeln!(f, "{}", unknown_tag)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MasterPlaylist {
+    /// Writes this playlist as m3u8 text to the given writer.
+    pub fn write_to(&self, mut writer: impl io::Write) -> io::Result<()> {
+        write!(writer, "{}", self)
+    }
+}
@@ -0,0 +1,162 @@
+use crate::models::{IFrame, MasterPlaylist, MediaTag, VariantStream};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// An attribute that variant streams, I-frames and media tags can be sorted on. Each key only
+/// applies to some of them, and the others keep their order when sorted on it.
+pub enum SortKey {
+    Bandwidth,
+    AverageBandwidth,
+    Resolution,
+    FrameRate,
+    Score,
+    Codecs,
+    /// The GROUP-ID of the audio renditions.
+    Audio,
+    /// The GROUP-ID of a media tag.
+    Group,
+    /// The LANGUAGE of a media tag.
+    Language,
+    /// The NAME of a media tag.
+    Name,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// One key of a sort, along with which way it should be ordered.
+pub struct SortBy {
+    pub key: SortKey,
+    pub order: SortOrder,
+}
+
+impl SortBy {
+    pub fn ascending(key: SortKey) -> Self {
+        Self {
+            key,
+            order: SortOrder::Ascending,
+        }
+    }
+
+    pub fn descending(key: SortKey) -> Self {
+        Self {
+            key,
+            order: SortOrder::Descending,
+        }
+    }
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    /// Parses a key like `bandwidth`, optionally followed by `:asc` or `:desc`.
+    fn from_str(sort_by: &str) -> Result<Self, Self::Err> {
+        let mut parts = sort_by.splitn(2, ':');
+        let key = match parts.next().unwrap_or_default() {
+            "bandwidth" => SortKey::Bandwidth,
+            "average-bandwidth" => SortKey::AverageBandwidth,
+            "resolution" => SortKey::Resolution,
+            "frame-rate" => SortKey::FrameRate,
+            "score" => SortKey::Score,
+            "codecs" => SortKey::Codecs,
+            "audio" => SortKey::Audio,
+            "group" => SortKey::Group,
+            "language" => SortKey::Language,
+            "name" => SortKey::Name,
+            key => return Err(format!("Unknown sort key: {}", key)),
+        };
+        let order = match parts.next() {
+            None | Some("asc") => SortOrder::Ascending,
+            Some("desc") => SortOrder::Descending,
+            Some(order) => return Err(format!("Unknown sort order: {}", order)),
+        };
+        Ok(Self { key, order })
+    }
+}
+
+/// Compares two entries on each key in turn, moving on to the next key only on a tie.
+fn compare_by<T>(
+    a: &T,
+    b: &T,
+    sort_by: &[SortBy],
+    compare_key: impl Fn(&T, &T, SortKey) -> Ordering,
+) -> Ordering {
+    sort_by.iter().fold(Ordering::Equal, |ordering, sort| {
+        ordering.then_with(|| match sort.order {
+            SortOrder::Ascending => compare_key(a, b, sort.key),
+            SortOrder::Descending => compare_key(a, b, sort.key).reverse(),
+        })
+    })
+}
+
+impl VariantStream {
+    fn compare_key(&self, other: &Self, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Bandwidth => self.bandwidth.cmp(&other.bandwidth),
+            SortKey::AverageBandwidth => self.average_bandwidth.cmp(&other.average_bandwidth),
+            SortKey::Resolution => self.resolution.cmp(&other.resolution),
+            SortKey::FrameRate => self.frame_rate.cmp(&other.frame_rate),
+            SortKey::Score => self.score.cmp(&other.score),
+            SortKey::Codecs => self.codecs.cmp(&other.codecs),
+            SortKey::Audio => self.audio.cmp(&other.audio),
+            SortKey::Group | SortKey::Language | SortKey::Name => Ordering::Equal,
+        }
+    }
+}
+
+impl IFrame {
+    /// I-frames have no average bandwidth, frame rate or audio, so those keys leave the order
+    /// unchanged.
+    fn compare_key(&self, other: &Self, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Bandwidth => self.bandwidth.cmp(&other.bandwidth),
+            SortKey::Resolution => self.resolution.cmp(&other.resolution),
+            SortKey::Score => self.score.cmp(&other.score),
+            SortKey::Codecs => self.codecs.cmp(&other.codecs),
+            SortKey::AverageBandwidth
+            | SortKey::FrameRate
+            | SortKey::Audio
+            | SortKey::Group
+            | SortKey::Language
+            | SortKey::Name => Ordering::Equal,
+        }
+    }
+}
+
+impl MediaTag {
+    /// Only the group, language and name keys apply to media tags.
+    fn compare_key(&self, other: &Self, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Group => self.group_id.cmp(&other.group_id),
+            SortKey::Language => self.language.cmp(&other.language),
+            SortKey::Name => self.name.cmp(&other.name),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+impl MasterPlaylist {
+    /// Sorts the variant streams by each key in turn. The sort is stable, so entries that
+    /// tie on every key keep their current order.
+    pub fn sort_variant_streams(&mut self, sort_by: &[SortBy]) {
+        self.variant_streams
+            .sort_by(|a, b| compare_by(a, b, sort_by, VariantStream::compare_key));
+    }
+
+    /// Sorts the I-frames by each key in turn, keeping the current order on ties.
+    pub fn sort_i_frames(&mut self, sort_by: &[SortBy]) {
+        self.i_frames
+            .sort_by(|a, b| compare_by(a, b, sort_by, IFrame::compare_key));
+    }
+
+    /// Sorts the media tags by each key in turn, keeping the current order on ties.
+    pub fn sort_media_tags(&mut self, sort_by: &[SortBy]) {
+        self.media_tags
+            .sort_by(|a, b| compare_by(a, b, sort_by, MediaTag::compare_key));
+    }
+}
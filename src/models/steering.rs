@@ -0,0 +1,154 @@
+#[cfg(feature = "serde")]
+use crate::errors::{Error, Result};
+use crate::models::{MasterPlaylist, VariantStream};
+use reqwest::Url;
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING-KEBAB-CASE"))]
+/// The JSON document a content steering server responds with, telling the client which
+/// pathway, e.g. which CDN, to play from.
+pub struct SteeringManifest {
+    /// Must be 1.
+    pub version: u32,
+    /// How many seconds the client should wait before reloading the manifest.
+    pub ttl: u64,
+    /// Where to reload the manifest from, if it isn't the SERVER-URI.
+    pub reload_uri: Option<String>,
+    /// Pathway IDs, most preferred first.
+    pub pathway_priority: Vec<String>,
+    /// New pathways made by copying an existing one onto other hosts.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pathway_clones: Vec<PathwayClone>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING-KEBAB-CASE"))]
+/// A pathway made by copying the variant streams of another pathway and rewriting their URIs.
+pub struct PathwayClone {
+    /// The pathway whose variant streams are copied.
+    pub base_id: String,
+    /// The ID of the new pathway.
+    pub id: String,
+    pub uri_replacement: UriReplacement,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING-KEBAB-CASE"))]
+/// How to rewrite the URIs of a cloned pathway.
+pub struct UriReplacement {
+    /// Replaces the host of each URI.
+    pub host: Option<String>,
+    /// Added to the query of each URI, replacing any parameters with the same name.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub query_parameters: BTreeMap<String, String>,
+    /// Replacement URIs for variant streams, keyed by STABLE-VARIANT-ID.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub per_variant_uris: BTreeMap<String, String>,
+    /// Replacement URIs for renditions, keyed by STABLE-RENDITION-ID.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub per_rendition_uris: BTreeMap<String, String>,
+}
+
+#[cfg(feature = "serde")]
+impl SteeringManifest {
+    /// Reads the JSON document a content steering server responds with.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let manifest: Self = serde_json::from_str(json)
+            .map_err(|e| Error::HLSFormat(format!("Invalid steering manifest: {}", e)))?;
+        if manifest.version != 1 {
+            return Err(Error::HLSFormat(format!(
+                "Unsupported steering manifest VERSION {}",
+                manifest.version
+            )));
+        }
+        Ok(manifest)
+    }
+}
+
+impl UriReplacement {
+    /// Rewrites the URI of a variant stream. Relative URIs are resolved against the URL of the
+    /// playlist first, and without one only PER-VARIANT-URIS applies to them.
+    fn variant_uri(&self, variant_stream: &VariantStream, playlist_url: Option<&str>) -> String {
+        if let Some(uri) = variant_stream
+            .stable_variant_id()
+            .and_then(|id| self.per_variant_uris.get(id))
+        {
+            return uri.clone();
+        }
+        let resolved = match playlist_url.map(Url::parse) {
+            Some(Ok(base)) => base.join(variant_stream.uri()),
+            _ => Url::parse(variant_stream.uri()),
+        };
+        let mut url = match resolved {
+            Ok(url) => url,
+            Err(_) => return variant_stream.uri().to_string(),
+        };
+        if let Some(host) = &self.host {
+            // Only fails for URLs that can't have a host, which a playlist URI won't be
+            let _ = url.set_host(Some(host));
+        }
+        if !self.query_parameters.is_empty() {
+            let kept: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(name, _)| !self.query_parameters.contains_key(name.as_ref()))
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect();
+            url.query_pairs_mut()
+                .clear()
+                .extend_pairs(kept)
+                .extend_pairs(&self.query_parameters);
+        }
+        url.to_string()
+    }
+}
+
+impl MasterPlaylist {
+    /// The variant streams of the most preferred pathway in a steering manifest that this
+    /// playlist can play, including pathways cloned from one of its own. Empty if none of the
+    /// pathways are available. `playlist_url` is where this playlist was loaded from, which
+    /// relative URIs of cloned variant streams are resolved against.
+    pub fn steered_variant_streams(
+        &self,
+        manifest: &SteeringManifest,
+        playlist_url: Option<&str>,
+    ) -> Vec<VariantStream> {
+        for pathway in &manifest.pathway_priority {
+            let variant_streams: Vec<VariantStream> = self
+                .variant_streams
+                .iter()
+                .filter(|variant_stream| variant_stream.pathway() == pathway)
+                .cloned()
+                .collect();
+            if !variant_streams.is_empty() {
+                return variant_streams;
+            }
+
+            let clone = manifest
+                .pathway_clones
+                .iter()
+                .find(|clone| &clone.id == pathway);
+            if let Some(clone) = clone {
+                let variant_streams: Vec<VariantStream> = self
+                    .variant_streams
+                    .iter()
+                    .filter(|variant_stream| variant_stream.pathway() == clone.base_id)
+                    .map(|variant_stream| VariantStream {
+                        uri: clone
+                            .uri_replacement
+                            .variant_uri(variant_stream, playlist_url),
+                        pathway_id: Some(clone.id.clone()),
+                        ..variant_stream.clone()
+                    })
+                    .collect();
+                if !variant_streams.is_empty() {
+                    return variant_streams;
+                }
+            }
+        }
+        vec![]
+    }
+}
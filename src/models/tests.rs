@@ -1,5 +1,7 @@
 use super::*;
-use std::collections::HashMap;
+use crate::errors::Location;
+use crate::parser::Tag;
+use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 
 #[test]
@@ -7,7 +9,7 @@ fn builds_variant_stream_from_parser_type() {
     let parsed_tags = given_parsed_types_of_each_tag();
 
     // When we convert them into a MasterPlaylist
-    let typed: MasterPlaylist = parsed_tags.try_into().unwrap();
+    let typed: MasterPlaylist = located(parsed_tags).try_into().unwrap();
 
     // Then we can get the converted variant stream
     assert_eq!(
@@ -29,27 +31,51 @@ fn builds_variant_stream_from_parser_type() {
 fn rejects_bad_variant_stream() {
     let bad_tag = vec![("EXT-X-STREAM-INF".to_string(), HashMap::new())];
 
-    let typed = MasterPlaylist::try_from(bad_tag);
+    let typed = MasterPlaylist::try_from(located(bad_tag));
 
-    assert!(matches!(typed.unwrap_err(), Error::HLSFormat { .. }))
+    assert!(matches!(typed.unwrap_err(), Error::HLSTag { .. }))
 }
 
 #[test]
 fn rejects_bad_i_frame() {
     let bad_tag = vec![("EXT-X-I-FRAME-STREAM-INF".to_string(), HashMap::new())];
 
-    let typed = MasterPlaylist::try_from(bad_tag);
+    let typed = MasterPlaylist::try_from(located(bad_tag));
 
-    assert!(matches!(typed.unwrap_err(), Error::HLSFormat { .. }))
+    assert!(matches!(typed.unwrap_err(), Error::HLSTag { .. }))
 }
 
 #[test]
 fn rejects_bad_media_tag() {
     let bad_tag = vec![("EXT-X-MEDIA".to_string(), HashMap::new())];
 
-    let typed = MasterPlaylist::try_from(bad_tag);
+    let typed = MasterPlaylist::try_from(located(bad_tag));
 
-    assert!(matches!(typed.unwrap_err(), Error::HLSFormat { .. }))
+    assert!(matches!(typed.unwrap_err(), Error::HLSTag { .. }))
+}
+
+#[test]
+fn rejects_variant_stream_without_bandwidth() {
+    let (tag, mut attrs) = given_parsed_types_of_each_tag().remove(0);
+    attrs.remove("BANDWIDTH");
+
+    let typed = MasterPlaylist::try_from(located(vec![(tag, attrs)]));
+
+    assert!(matches!(typed.unwrap_err(), Error::HLSTag { .. }))
+}
+
+#[test]
+fn builds_media_tag_without_optional_attributes() {
+    let (_, mut attrs) = given_a_parsed_media_tag();
+    for optional in &["LANGUAGE", "DEFAULT", "AUTOSELECT", "CHANNELS", "URI"] {
+        attrs.remove(*optional);
+    }
+
+    let media_tag = MediaTag::try_from(attrs).unwrap();
+
+    assert_eq!(media_tag.language, None);
+    assert_eq!(media_tag.channels, None);
+    assert_eq!(media_tag.uri, None);
 }
 
 #[test]
@@ -58,11 +84,1021 @@ fn rejects_bad_media_type() {
     attrs.insert("TYPE".to_string(), "NOTAREALTYPE".to_string());
     let bad_tag = vec![(tag, attrs)];
 
-    let typed = MasterPlaylist::try_from(bad_tag);
+    let typed = MasterPlaylist::try_from(located(bad_tag));
+
+    assert!(matches!(typed.unwrap_err(), Error::HLSTag { .. }))
+}
+
+#[test]
+fn builds_media_playlist_from_parser_type() {
+    let parsed_tags = given_parsed_media_playlist_tags();
+
+    let typed: MediaPlaylist = located(parsed_tags).try_into().unwrap();
+
+    assert_eq!(typed.target_duration, 10);
+    assert_eq!(typed.media_sequence, 7);
+    assert_eq!(typed.playlist_type, Some(PlaylistType::Vod));
+    assert!(typed.end_list);
+    assert_eq!(typed.segments, vec![Segment::example()]);
+}
+
+#[test]
+fn applies_keys_and_maps_to_the_segments_that_follow_them() {
+    let mut tags = given_parsed_media_playlist_tags();
+    let segment = given_a_parsed_segment();
+    let key = |method: &str, key_format: Option<&str>| {
+        let mut attributes = HashMap::new();
+        attributes.insert("METHOD".to_string(), method.to_string());
+        attributes.insert("URI".to_string(), "https://keys.example.com/1".to_string());
+        if let Some(key_format) = key_format {
+            attributes.insert("KEYFORMAT".to_string(), key_format.to_string());
+        }
+        ("EXT-X-KEY".to_string(), attributes)
+    };
+    let mut map_attributes = HashMap::new();
+    map_attributes.insert("URI".to_string(), "init.mp4".to_string());
+    map_attributes.insert("BYTERANGE".to_string(), "720@0".to_string());
+
+    tags.splice(
+        4..5,
+        vec![
+            ("EXT-X-MAP".to_string(), map_attributes),
+            key("SAMPLE-AES", None),
+            key("SAMPLE-AES", Some("com.apple.streamingkeydelivery")),
+            segment.clone(),
+            key("AES-128", None),
+            segment.clone(),
+            ("EXT-X-KEY".to_string(), {
+                let mut attributes = HashMap::new();
+                attributes.insert("METHOD".to_string(), "NONE".to_string());
+                attributes
+            }),
+            segment,
+        ],
+    );
+
+    let typed: MediaPlaylist = located(tags).try_into().unwrap();
+
+    let methods: Vec<Vec<(KeyMethod, &str)>> = typed
+        .segments
+        .iter()
+        .map(|segment| {
+            segment
+                .keys()
+                .iter()
+                .map(|key| (key.method(), key.key_format()))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        methods,
+        vec![
+            vec![
+                (KeyMethod::SampleAes, "identity"),
+                (KeyMethod::SampleAes, "com.apple.streamingkeydelivery"),
+            ],
+            vec![
+                (KeyMethod::SampleAes, "com.apple.streamingkeydelivery"),
+                (KeyMethod::Aes128, "identity"),
+            ],
+            vec![],
+        ]
+    );
+    let map = typed.segments[2].map().unwrap();
+    assert_eq!(map.uri(), "init.mp4");
+    assert_eq!(
+        map.byte_range(),
+        Some(ByteRange {
+            length: 720,
+            offset: Some(0)
+        })
+    );
+    assert_eq!(
+        map.to_string(),
+        "#EXT-X-MAP:URI=\"init.mp4\",BYTERANGE=\"720@0\""
+    );
+}
+
+#[test]
+fn requires_a_uri_for_encryption_keys() {
+    let mut attributes = HashMap::new();
+    attributes.insert("METHOD".to_string(), "AES-128".to_string());
+
+    let key = Key::try_from(attributes);
+
+    assert_eq!(
+        key.unwrap_err(),
+        Error::HLSFormat("HLS missing URI".to_string())
+    );
+}
+
+#[test]
+fn rejects_media_playlist_without_target_duration() {
+    let tags = vec![given_a_parsed_segment()];
+
+    let typed = MediaPlaylist::try_from(located(tags));
 
     assert!(matches!(typed.unwrap_err(), Error::HLSFormat { .. }))
 }
 
+#[test]
+fn detects_playlist_kind() {
+    let master = Playlist::try_from(located(given_parsed_types_of_each_tag())).unwrap();
+    let media = Playlist::try_from(located(given_parsed_media_playlist_tags())).unwrap();
+
+    assert!(matches!(master, Playlist::Master(_)));
+    assert!(matches!(media, Playlist::Media(_)));
+}
+
+#[test]
+fn serializes_variant_stream_with_uri_line() {
+    assert_eq!(
+        VariantStream::example().to_string(),
+        "#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=11960x55540,FRAME-RATE=23.970,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/1650k/vod.m3u8"
+    );
+}
+
+#[test]
+fn serializes_media_tag() {
+    assert_eq!(
+        MediaTag::example().to_string(),
+        "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-64k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_64k/vod.m3u8\""
+    );
+}
+
+#[test]
+fn serializes_i_frame() {
+    assert_eq!(
+        IFrame::example().to_string(),
+        "#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/900k/vod-iframe.m3u8\""
+    );
+}
+
+#[test]
+fn serializes_master_playlist_with_extm3u_first() {
+    let typed: MasterPlaylist = located(given_parsed_types_of_each_tag())
+        .try_into()
+        .unwrap();
+
+    let serialized = typed.to_string();
+
+    assert!(serialized.starts_with("#EXTM3U\n#EXT-X-MEDIA:"));
+    assert_eq!(serialized.matches("#EXTM3U").count(), 1);
+}
+
+#[test]
+fn reports_tag_and_location_of_format_errors() {
+    let mut tags = given_parsed_types_of_each_tag();
+    tags[2].1.remove("GROUP-ID");
+
+    let typed = MasterPlaylist::try_from(located(tags));
+
+    match typed.unwrap_err() {
+        Error::HLSTag {
+            tag,
+            message,
+            location,
+        } => {
+            assert_eq!(tag, "EXT-X-MEDIA");
+            assert_eq!(message, "HLS missing GROUP-ID");
+            assert_eq!(location.line, 3);
+        }
+        other => panic!("Expected an HLSTag error, got {:?}", other),
+    }
+}
+
+#[test]
+fn keeps_unknown_tags_with_values() {
+    let mut tags = given_parsed_types_of_each_tag();
+    tags.push(given_a_parsed_value_tag("EXT-X-BITMOVIN-STREAM", "42"));
+
+    let typed: MasterPlaylist = located(tags).try_into().unwrap();
+
+    assert_eq!(
+        typed.unknown_tags,
+        vec![UnknownTag {
+            name: "EXT-X-BITMOVIN-STREAM".to_string(),
+            value: Some("42".to_string()),
+            line: 5,
+        }]
+    );
+    assert!(typed.to_string().contains("\n#EXT-X-BITMOVIN-STREAM:42\n"));
+}
+
+#[test]
+fn sorts_variant_streams_by_multiple_keys() {
+    let mut playlist = MasterPlaylist {
+        variant_streams: vec![
+            VariantStream::with_bandwidth_and_audio(300, "aac"),
+            VariantStream::with_bandwidth_and_audio(100, "atmos"),
+            VariantStream::with_bandwidth_and_audio(200, "aac"),
+        ],
+        ..MasterPlaylist::default()
+    };
+
+    playlist.sort_variant_streams(&[
+        SortBy::ascending(SortKey::Audio),
+        SortBy::descending(SortKey::Bandwidth),
+    ]);
+
+    let sorted: Vec<(u32, Option<&str>)> = playlist
+        .variant_streams
+        .iter()
+        .map(|variant| (variant.bandwidth(), variant.audio()))
+        .collect();
+    assert_eq!(
+        sorted,
+        vec![(300, Some("aac")), (200, Some("aac")), (100, Some("atmos"))]
+    );
+}
+
+#[test]
+fn sorts_media_tags_by_group_and_language() {
+    let media_tag = |group_id: &str, language: &str| MediaTag {
+        group_id: group_id.to_string(),
+        language: Some(language.to_string()),
+        ..MediaTag::example()
+    };
+    let mut playlist = MasterPlaylist {
+        media_tags: vec![
+            media_tag("aac-64k", "fr"),
+            media_tag("aac-128k", "en"),
+            media_tag("aac-64k", "en"),
+        ],
+        ..MasterPlaylist::default()
+    };
+
+    playlist.sort_media_tags(&[
+        SortBy::ascending(SortKey::Group),
+        SortBy::descending(SortKey::Language),
+        SortBy::ascending(SortKey::Bandwidth),
+    ]);
+
+    let sorted: Vec<(&str, Option<&str>)> = playlist
+        .media_tags
+        .iter()
+        .map(|media_tag| (media_tag.group_id(), media_tag.language()))
+        .collect();
+    assert_eq!(
+        sorted,
+        vec![
+            ("aac-128k", Some("en")),
+            ("aac-64k", Some("fr")),
+            ("aac-64k", Some("en"))
+        ]
+    );
+    assert_eq!("name:desc".parse(), Ok(SortBy::descending(SortKey::Name)));
+}
+
+#[test]
+fn preserves_document_order_when_asked() {
+    let mut tags = given_parsed_types_of_each_tag();
+    let (name, mut attrs) = tags[0].clone();
+    attrs.insert("URI".to_string(), "a/first.m3u8".to_string());
+    tags.push((name, attrs));
+    let options = ParseOptions {
+        preserve_order: true,
+        ..ParseOptions::default()
+    };
+
+    let typed = MasterPlaylist::from_tags(located(tags), &options).unwrap();
+
+    assert_eq!(typed.variant_streams[0].uri(), "hdr10/unenc/1650k/vod.m3u8");
+    assert_eq!(typed.variant_streams[1].uri(), "a/first.m3u8");
+}
+
+#[test]
+fn parses_sort_by_from_str() {
+    assert_eq!(
+        "average-bandwidth:desc".parse(),
+        Ok(SortBy::descending(SortKey::AverageBandwidth))
+    );
+    assert_eq!("codecs".parse(), Ok(SortBy::ascending(SortKey::Codecs)));
+    assert!("codecs:sideways".parse::<SortBy>().is_err());
+}
+
+#[test]
+fn validates_a_conforming_playlist() {
+    let mut tags = given_parsed_types_of_each_tag();
+    tags.rotate_right(3);
+    let (name, mut attrs) = given_a_parsed_media_tag();
+    attrs.insert("GROUP-ID".to_string(), "aac-128k".to_string());
+    tags.push((name, attrs));
+
+    let typed: MasterPlaylist = located(tags).try_into().unwrap();
+
+    assert_eq!(validate(&typed), vec![]);
+}
+
+#[test]
+fn reports_each_broken_rule() {
+    let closed_captions = MediaTag {
+        media_type: MediaType::ClosedCaptions,
+        group_id: "cc".to_string(),
+        ..MediaTag::example()
+    };
+    let playlist = MasterPlaylist {
+        variant_streams: vec![VariantStream {
+            average_bandwidth: Some(3000000),
+            ..VariantStream::example()
+        }],
+        media_tags: vec![MediaTag::example(), MediaTag::example(), closed_captions],
+        ..MasterPlaylist::default()
+    };
+
+    let diagnostics = validate(&playlist);
+
+    let rules: Vec<(Rule, Severity)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.rule, diagnostic.severity))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            (Rule::ExtM3uFirst, Severity::Error),
+            (Rule::GroupReference, Severity::Error),
+            (Rule::AverageBandwidth, Severity::Warning),
+            (Rule::SingleDefault, Severity::Error),
+            (Rule::ClosedCaptionsUri, Severity::Error),
+            (Rule::InstreamId, Severity::Error),
+        ]
+    );
+    assert_eq!(
+        diagnostics[1].entry,
+        Entry::VariantStream(&playlist.variant_streams[0])
+    );
+    assert_eq!(
+        diagnostics[1].to_string(),
+        "error[group-reference]: Variant stream hdr10/unenc/1650k/vod.m3u8 refers to AUDIO group \"aac-128k\" but there is no such EXT-X-MEDIA"
+    );
+}
+
+#[test]
+fn converts_subtitle_and_closed_caption_attributes() {
+    let mut attributes = HashMap::new();
+    attributes.insert("TYPE".to_string(), "CLOSED-CAPTIONS".to_string());
+    attributes.insert("GROUP-ID".to_string(), "cc".to_string());
+    attributes.insert("NAME".to_string(), "English".to_string());
+    attributes.insert("ASSOC-LANGUAGE".to_string(), "en-US".to_string());
+    attributes.insert("INSTREAM-ID".to_string(), "SERVICE63".to_string());
+    attributes.insert(
+        "CHARACTERISTICS".to_string(),
+        "public.accessibility.transcribes-spoken-dialog,public.easy-to-read".to_string(),
+    );
+    attributes.insert("STABLE-RENDITION-ID".to_string(), "cc-en".to_string());
+
+    let media_tag = MediaTag::try_from(attributes.clone()).unwrap();
+
+    assert_eq!(media_tag.instream_id(), Some(InstreamId::Service(63)));
+    assert_eq!(media_tag.characteristics().unwrap().len(), 2);
+    assert_eq!(
+        media_tag.to_string(),
+        "#EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID=\"cc\",NAME=\"English\",ASSOC-LANGUAGE=\"en-US\",STABLE-RENDITION-ID=\"cc-en\",INSTREAM-ID=\"SERVICE63\",CHARACTERISTICS=\"public.accessibility.transcribes-spoken-dialog,public.easy-to-read\""
+    );
+
+    for instream_id in &["CC5", "SERVICE0", "CC"] {
+        attributes.insert("INSTREAM-ID".to_string(), instream_id.to_string());
+        assert!(MediaTag::try_from(attributes.clone()).is_err());
+    }
+
+    attributes.insert("INSTREAM-ID".to_string(), "CC1".to_string());
+    attributes.insert("TYPE".to_string(), "SUBTITLES".to_string());
+    assert_eq!(
+        MediaTag::try_from(attributes.clone())
+            .unwrap_err()
+            .to_string(),
+        "Invalid HLS: INSTREAM-ID is only allowed for CLOSED-CAPTIONS, not SUBTITLES"
+    );
+    attributes.remove("INSTREAM-ID");
+    attributes.insert("TYPE".to_string(), "CLOSED-CAPTIONS".to_string());
+    assert!(MediaTag::try_from(attributes).is_err());
+}
+
+#[test]
+fn keeps_unknown_video_ranges_when_lenient() {
+    let mut tags = given_parsed_types_of_each_tag();
+    tags[0]
+        .1
+        .insert("VIDEO-RANGE".to_string(), "HDR-NEXT".to_string());
+    tags[3]
+        .1
+        .insert("VIDEO-RANGE".to_string(), "HLG".to_string());
+
+    let strict = MasterPlaylist::try_from(located(tags.clone())).unwrap_err();
+    assert!(strict
+        .to_string()
+        .contains("Invalid video range specified: HDR-NEXT"));
+
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let lenient = MasterPlaylist::from_tags(located(tags), &options).unwrap();
+    let variant_stream = &lenient.variant_streams[0];
+    assert_eq!(
+        variant_stream.video_range(),
+        Some(&VideoRange::Other("HDR-NEXT".to_string()))
+    );
+    assert_eq!(lenient.i_frames[0].video_range(), Some(&VideoRange::HLG));
+    assert!(variant_stream.to_string().contains("VIDEO-RANGE=HDR-NEXT"));
+
+    let warnings: Vec<(Rule, Severity)> = validate(&lenient)
+        .iter()
+        .filter(|diagnostic| diagnostic.entry == Entry::VariantStream(variant_stream))
+        .map(|diagnostic| (diagnostic.rule, diagnostic.severity))
+        .collect();
+    assert!(warnings.contains(&(Rule::UnknownValue, Severity::Warning)));
+}
+
+#[test]
+fn converts_the_full_stream_inf_attribute_set() {
+    let variant_str = "#EXT-X-STREAM-INF:BANDWIDTH=2483789,CODECS=\"hvc1.2.4.L90.90\",HDCP-LEVEL=TYPE-1,VIDEO=\"cam-1\",SCORE=1.5,SUPPLEMENTAL-CODECS=\"dvh1.08.07/db4h\",ALLOWED-CPC=\"com.example.drm1:SMART-TV/PC,com.example.drm2:HW\",STABLE-VARIANT-ID=\"hd\",PATHWAY-ID=\"CDN-A\",REQ-VIDEO-LAYOUT=\"CH-STEREO,CH-MONO\"\nhd/vod.m3u8";
+    let attributes = crate::parser::HLSParser {}
+        .parse(variant_str)
+        .unwrap()
+        .remove(0)
+        .attributes;
+
+    let variant = VariantStream::try_from(attributes).unwrap();
+
+    assert_eq!(variant.hdcp_level(), Some(HdcpLevel::Type1));
+    assert_eq!(variant.score().unwrap().value(), 1.5);
+    let supplemental = variant
+        .supplemental_codecs()
+        .unwrap()
+        .iter()
+        .next()
+        .unwrap();
+    assert_eq!(
+        supplemental.format(),
+        CodecFormat::DolbyVision(DolbyVision {
+            profile: 8,
+            level: 7
+        })
+    );
+    assert_eq!(supplemental.brands().collect::<Vec<_>>(), vec!["db4h"]);
+    assert_eq!(
+        variant.allowed_cpc().unwrap()[0],
+        AllowedCpc {
+            key_format: "com.example.drm1".to_string(),
+            cpc: vec!["SMART-TV".to_string(), "PC".to_string()],
+        }
+    );
+    assert_eq!(
+        variant.req_video_layout(),
+        Some(&[VideoLayout::ChStereo, VideoLayout::ChMono][..])
+    );
+    assert_eq!(variant.to_string(), variant_str);
+}
+
+#[test]
+fn carries_stream_attributes_over_to_i_frames() {
+    let (_, mut attributes) = given_parsed_types_of_each_tag().remove(3);
+    attributes.insert("HDCP-LEVEL".to_string(), "NONE".to_string());
+    attributes.insert("PATHWAY-ID".to_string(), "CDN-B".to_string());
+
+    let i_frame = IFrame::try_from(attributes.clone()).unwrap();
+
+    assert_eq!(i_frame.hdcp_level(), Some(HdcpLevel::None));
+    assert_eq!(i_frame.pathway_id(), Some("CDN-B"));
+    assert!(i_frame.to_string().ends_with(
+        "HDCP-LEVEL=NONE,PATHWAY-ID=\"CDN-B\",URI=\"hdr10/unenc/900k/vod-iframe.m3u8\""
+    ));
+
+    attributes.insert("SCORE".to_string(), "-1".to_string());
+    assert!(IFrame::try_from(attributes).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serializes_with_documented_field_names() {
+    let media_tag = MediaTag {
+        media_type: MediaType::ClosedCaptions,
+        uri: None,
+        ..MediaTag::example()
+    };
+
+    let json = serde_json::to_value(&media_tag).unwrap();
+
+    assert_eq!(json["media_type"], "CLOSED-CAPTIONS");
+    assert_eq!(json["group_id"], "aac-64k");
+    assert_eq!(serde_json::from_value::<MediaTag>(json).unwrap(), media_tag);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serializes_enumerated_values_with_their_hls_spelling() {
+    let mut attributes = HashMap::new();
+    attributes.insert("BANDWIDTH".to_string(), "1280000".to_string());
+    attributes.insert("URI".to_string(), "low.m3u8".to_string());
+    attributes.insert("HDCP-LEVEL".to_string(), "TYPE-1".to_string());
+    attributes.insert(
+        "REQ-VIDEO-LAYOUT".to_string(),
+        "CH-STEREO,CH-MONO,PROJ-EQUI".to_string(),
+    );
+    let variant_stream = VariantStream::try_from(attributes).unwrap();
+
+    let json = serde_json::to_value(&variant_stream).unwrap();
+
+    assert_eq!(json["hdcp_level"], "TYPE-1");
+    assert_eq!(
+        json["req_video_layout"],
+        serde_json::json!(["CH-STEREO", "CH-MONO", "PROJ-EQUI"])
+    );
+    assert_eq!(
+        serde_json::from_value::<VariantStream>(json).unwrap(),
+        variant_stream
+    );
+    assert_eq!(
+        serde_json::to_value(HdcpLevel::Type0).unwrap(),
+        serde_json::json!("TYPE-0")
+    );
+    assert_eq!(
+        serde_json::to_value(HdcpLevel::None).unwrap(),
+        serde_json::json!("NONE")
+    );
+}
+
+#[test]
+fn decodes_each_codec_in_a_codecs_list() {
+    let codecs = Codecs::from(
+        "avc1.64001F,hvc1.2.4.L123.B0,mp4a.40.2,ec-3,ac-3,Opus,av01.0.04M.10,vp09.00.41.08,dvh1.05.06",
+    );
+
+    let formats: Vec<CodecFormat> = codecs.formats().collect();
+    assert_eq!(
+        formats,
+        vec![
+            CodecFormat::Avc(Avc {
+                profile: AvcProfile::High,
+                constraints: 0,
+                level: Level::new(3, 1),
+            }),
+            CodecFormat::Hevc(Hevc {
+                profile: HevcProfile::Main10,
+                tier: Tier::Main,
+                level: Level::new(4, 1),
+            }),
+            CodecFormat::Aac(AacObjectType::Lc),
+            CodecFormat::Ec3,
+            CodecFormat::Ac3,
+            CodecFormat::Opus,
+            CodecFormat::Av1(Av1 {
+                profile: 0,
+                level: Level::new(3, 0),
+                tier: Tier::Main,
+                bit_depth: 10,
+            }),
+            CodecFormat::Vp9(Vp9 {
+                profile: 0,
+                level: Level::new(4, 1),
+                bit_depth: 8,
+            }),
+            CodecFormat::DolbyVision(DolbyVision {
+                profile: 5,
+                level: 6,
+            }),
+        ]
+    );
+}
+
+#[test]
+fn keeps_unknown_codecs_as_written() {
+    let codecs = Codecs::from("stpp.ttml.im1t, hvc1.2.4.X63.90");
+
+    let raw: Vec<(&str, CodecFormat)> = codecs
+        .iter()
+        .map(|codec| (codec.raw(), codec.format()))
+        .collect();
+    assert_eq!(
+        raw,
+        vec![
+            ("stpp.ttml.im1t", CodecFormat::Unknown),
+            ("hvc1.2.4.X63.90", CodecFormat::Unknown)
+        ]
+    );
+    assert_eq!(codecs.to_string(), "stpp.ttml.im1t,hvc1.2.4.X63.90");
+}
+
+#[test]
+fn orders_frame_rates_numerically() {
+    let mut frame_rates: Vec<FrameRate> = ["59.94", "23.976", "29.97", "120"]
+        .iter()
+        .map(|frame_rate| frame_rate.parse::<f64>().unwrap().try_into().unwrap())
+        .collect();
+
+    frame_rates.sort();
+
+    let displayed: Vec<String> = frame_rates.iter().map(FrameRate::to_string).collect();
+    assert_eq!(displayed, vec!["23.976", "29.970", "59.940", "120.000"]);
+}
+
+#[test]
+fn rejects_frame_rates_that_are_not_positive() {
+    let mut tags = given_parsed_types_of_each_tag();
+    tags[0]
+        .1
+        .insert("FRAME-RATE".to_string(), "-23.976".to_string());
+
+    let error = MasterPlaylist::try_from(located(tags)).unwrap_err();
+
+    assert!(error.to_string().contains("Invalid frame rate"));
+    assert!(FrameRate::try_from(0.0).is_err());
+}
+
+#[test]
+fn converts_yes_no_attributes_to_bools() {
+    let (_, mut attributes) = given_a_parsed_media_tag();
+    attributes.insert("AUTOSELECT".to_string(), "NO".to_string());
+    attributes.remove("DEFAULT");
+
+    let media_tag = MediaTag::try_from(attributes.clone()).unwrap();
+
+    assert!(!media_tag.default());
+    assert!(!media_tag.autoselect());
+    assert!(!media_tag.to_string().contains("DEFAULT"));
+    assert!(media_tag.to_string().contains("AUTOSELECT=NO"));
+
+    attributes.insert("FORCED".to_string(), "yes".to_string());
+    assert_eq!(
+        MediaTag::try_from(attributes).unwrap_err(),
+        Error::HLSFormat("FORCED must be YES or NO, found yes".to_string())
+    );
+}
+
+#[test]
+fn requires_autoselect_for_default_renditions() {
+    let playlist = MasterPlaylist {
+        starts_with_extm3u: true,
+        media_tags: vec![MediaTag {
+            autoselect: Some(false),
+            ..MediaTag::example()
+        }],
+        ..MasterPlaylist::default()
+    };
+
+    let rules: Vec<Rule> = validate(&playlist)
+        .iter()
+        .map(|diagnostic| diagnostic.rule)
+        .collect();
+
+    assert_eq!(rules, vec![Rule::AutoselectWithDefault]);
+}
+
+#[test]
+fn parses_channel_parameters() {
+    let channels = |value: &str| Channels::try_from(&value.to_string());
+
+    let atmos = channels("16/JOC").unwrap();
+    assert_eq!(atmos.count, 16);
+    assert_eq!(atmos.spatial_audio, vec!["JOC"]);
+    assert!(atmos.is_immersive());
+    assert_eq!(atmos.to_string(), "16/JOC");
+
+    let binaural = channels("2/-/BINAURAL").unwrap();
+    assert!(binaural.spatial_audio.is_empty());
+    assert!(binaural.is_immersive());
+    assert_eq!(binaural.to_string(), "2/-/BINAURAL");
+
+    assert!(!channels("6").unwrap().is_immersive());
+    assert!(channels("JOC").is_err());
+}
+
+/// Gives each parsed tag a location as if it was found on its own line.
+#[test]
+fn converts_session_data_and_keys() {
+    let mut data_attributes = HashMap::new();
+    data_attributes.insert("DATA-ID".to_string(), "com.example.lyrics".to_string());
+    data_attributes.insert("URI".to_string(), "lyrics.bin".to_string());
+    data_attributes.insert("FORMAT".to_string(), "RAW".to_string());
+
+    let session_data = SessionData::try_from(data_attributes).unwrap();
+
+    assert_eq!(session_data.data_id(), "com.example.lyrics");
+    assert_eq!(session_data.value(), None);
+    assert_eq!(session_data.format(), SessionDataFormat::Raw);
+    assert_eq!(
+        session_data.to_string(),
+        "#EXT-X-SESSION-DATA:DATA-ID=\"com.example.lyrics\",URI=\"lyrics.bin\",FORMAT=RAW"
+    );
+
+    let mut key_attributes = HashMap::new();
+    key_attributes.insert("METHOD".to_string(), "AES-128".to_string());
+    key_attributes.insert("URI".to_string(), "https://keys.example.com/1".to_string());
+    key_attributes.insert("IV".to_string(), "0x1F".to_string());
+    key_attributes.insert("KEYFORMATVERSIONS".to_string(), "1/2".to_string());
+
+    let session_key = SessionKey::try_from(key_attributes.clone()).unwrap();
+
+    assert_eq!(session_key.method(), KeyMethod::Aes128);
+    assert_eq!(session_key.iv(), Some(InitializationVector(0x1f)));
+    assert_eq!(session_key.key_format(), "identity");
+    assert_eq!(
+        session_key.to_string(),
+        "#EXT-X-SESSION-KEY:METHOD=AES-128,URI=\"https://keys.example.com/1\",IV=0x0000000000000000000000000000001f,KEYFORMATVERSIONS=\"1/2\""
+    );
+
+    key_attributes.insert("IV".to_string(), "1F".to_string());
+    assert!(SessionKey::try_from(key_attributes).is_err());
+}
+
+#[test]
+fn requires_session_data_to_have_a_value_or_uri() {
+    let both = SessionData {
+        data_id: "com.example.title".to_string(),
+        value: Some("Example".to_string()),
+        uri: Some("title.json".to_string()),
+        language: None,
+        format: None,
+    };
+    let neither = SessionData {
+        value: None,
+        uri: None,
+        ..both.clone()
+    };
+    let playlist = MasterPlaylist {
+        starts_with_extm3u: true,
+        session_data: vec![both, neither],
+        ..MasterPlaylist::default()
+    };
+
+    let messages: Vec<String> = validate(&playlist)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    assert_eq!(
+        messages,
+        vec![
+            "error[session-data-value]: Session data \"com.example.title\" has both a VALUE and a URI",
+            "error[session-data-value]: Session data \"com.example.title\" has neither a VALUE nor a URI",
+        ]
+    );
+}
+
+#[test]
+fn steers_to_the_top_priority_pathway() {
+    let playlist = MasterPlaylist {
+        variant_streams: vec![
+            VariantStream {
+                uri: "https://cdn-a.example.com/low.m3u8?token=1".to_string(),
+                pathway_id: Some("cdn-a".to_string()),
+                ..VariantStream::example()
+            },
+            VariantStream {
+                uri: "https://cdn-b.example.com/low.m3u8".to_string(),
+                pathway_id: Some("cdn-b".to_string()),
+                ..VariantStream::example()
+            },
+        ],
+        ..MasterPlaylist::default()
+    };
+    let mut query_parameters = BTreeMap::new();
+    query_parameters.insert("token".to_string(), "2".to_string());
+    let manifest = SteeringManifest {
+        version: 1,
+        ttl: 300,
+        reload_uri: None,
+        pathway_priority: vec!["cdn-c".to_string(), "cdn-a".to_string()],
+        pathway_clones: vec![PathwayClone {
+            base_id: "cdn-a".to_string(),
+            id: "cdn-c".to_string(),
+            uri_replacement: UriReplacement {
+                host: Some("cdn-c.example.com".to_string()),
+                query_parameters,
+                ..UriReplacement::default()
+            },
+        }],
+    };
+
+    let steered = playlist.steered_variant_streams(&manifest, None);
+
+    let uris: Vec<(&str, &str)> = steered
+        .iter()
+        .map(|variant_stream| (variant_stream.pathway(), variant_stream.uri()))
+        .collect();
+    assert_eq!(
+        uris,
+        vec![("cdn-c", "https://cdn-c.example.com/low.m3u8?token=2")]
+    );
+}
+
+#[test]
+fn resolves_relative_uris_of_cloned_pathways() {
+    let playlist = MasterPlaylist {
+        variant_streams: vec![VariantStream {
+            uri: "hdr10/low.m3u8".to_string(),
+            pathway_id: Some("cdn-a".to_string()),
+            ..VariantStream::example()
+        }],
+        ..MasterPlaylist::default()
+    };
+    let manifest = SteeringManifest {
+        version: 1,
+        ttl: 300,
+        reload_uri: None,
+        pathway_priority: vec!["cdn-b".to_string()],
+        pathway_clones: vec![PathwayClone {
+            base_id: "cdn-a".to_string(),
+            id: "cdn-b".to_string(),
+            uri_replacement: UriReplacement {
+                host: Some("cdn-b.example.com".to_string()),
+                ..UriReplacement::default()
+            },
+        }],
+    };
+
+    let resolved = playlist
+        .steered_variant_streams(&manifest, Some("https://cdn-a.example.com/vod/master.m3u8"));
+    let unresolved = playlist.steered_variant_streams(&manifest, None);
+
+    assert_eq!(
+        resolved[0].uri(),
+        "https://cdn-b.example.com/vod/hdr10/low.m3u8"
+    );
+    assert_eq!(unresolved[0].uri(), "hdr10/low.m3u8");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn reads_steering_manifests_from_json() {
+    let manifest = SteeringManifest::from_json(
+        r#"{
+            "VERSION": 1,
+            "TTL": 300,
+            "PATHWAY-PRIORITY": ["cdn-c", "cdn-a"],
+            "PATHWAY-CLONES": [{
+                "BASE-ID": "cdn-a",
+                "ID": "cdn-c",
+                "URI-REPLACEMENT": {"HOST": "cdn-c.example.com", "QUERY-PARAMETERS": {"token": "2"}}
+            }]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(manifest.pathway_priority, vec!["cdn-c", "cdn-a"]);
+    assert_eq!(
+        manifest.pathway_clones[0].uri_replacement.query_parameters["token"],
+        "2"
+    );
+    assert_eq!(manifest.reload_uri, None);
+    assert!(SteeringManifest::from_json(r#"{"VERSION": 1}"#).is_err());
+    assert_eq!(
+        SteeringManifest::from_json(r#"{"VERSION": 2, "TTL": 300, "PATHWAY-PRIORITY": []}"#)
+            .unwrap_err(),
+        Error::HLSFormat("Unsupported steering manifest VERSION 2".to_string())
+    );
+}
+
+#[test]
+fn reports_broken_low_latency_rules() {
+    let mut part_attributes = HashMap::new();
+    part_attributes.insert("DURATION".to_string(), "0.5".to_string());
+    part_attributes.insert("URI".to_string(), "part.0.mp4".to_string());
+    let playlist = MediaPlaylist {
+        part_target: Some(0.334),
+        parts: vec![Part::try_from(part_attributes).unwrap()],
+        server_control: Some(ServerControl {
+            part_hold_back: Some(0.5),
+            ..ServerControl::default()
+        }),
+        ..MediaPlaylist::default()
+    };
+
+    let diagnostics = validate_media_playlist(&playlist);
+
+    let messages: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "error[part-target]: Part part.0.mp4 lasts 0.5 seconds, longer than the PART-TARGET of 0.334",
+            "error[can-block-reload]: Playlist has partial segments but EXT-X-SERVER-CONTROL doesn't have CAN-BLOCK-RELOAD=YES",
+            "error[part-hold-back]: PART-HOLD-BACK of 0.5 is less than twice the PART-TARGET of 0.334",
+        ]
+    );
+    assert_eq!(diagnostics[0].entry, Entry::Part(&playlist.parts[0]));
+
+    let without_part_inf = MediaPlaylist {
+        part_target: None,
+        server_control: Some(ServerControl {
+            can_block_reload: Some(true),
+            ..ServerControl::default()
+        }),
+        ..playlist
+    };
+    let rules: Vec<Rule> = validate_media_playlist(&without_part_inf)
+        .iter()
+        .map(|diagnostic| diagnostic.rule)
+        .collect();
+    assert_eq!(rules, vec![Rule::PartTarget]);
+}
+
+#[test]
+fn decodes_scte35_commands_of_date_ranges() {
+    let mut attributes = HashMap::new();
+    attributes.insert("ID".to_string(), "signal-1".to_string());
+    attributes.insert(
+        "SCTE35-CMD".to_string(),
+        "0xFC301600000000000000FFF00506FE72BD00500000870A2851".to_string(),
+    );
+    attributes.insert(
+        "SCTE35-IN".to_string(),
+        "0xFC301600000000000000FFF005054800008FFF0000CDBAC052".to_string(),
+    );
+
+    let date_range = DateRange::try_from(attributes).unwrap();
+
+    assert_eq!(
+        date_range.scte35_cmd().unwrap().command,
+        SpliceCommand::TimeSignal {
+            pts_time: Some(0x72BD0050)
+        }
+    );
+    let scte35_in = date_range.scte35_in().unwrap();
+    assert_eq!(scte35_in.tier, 0xFFF);
+    assert_eq!(
+        scte35_in.command,
+        SpliceCommand::Insert(SpliceInsert {
+            event_id: 0x4800008F,
+            cancel: true,
+            ..SpliceInsert::default()
+        })
+    );
+    assert!(!date_range.end_on_next());
+    assert!(date_range.client_attributes().is_empty());
+}
+
+#[test]
+fn rejects_invalid_scte35_payloads() {
+    let date_range = |payload: &str| {
+        let mut attributes = HashMap::new();
+        attributes.insert("ID".to_string(), "splice-1".to_string());
+        attributes.insert("SCTE35-OUT".to_string(), payload.to_string());
+        DateRange::try_from(attributes)
+    };
+
+    assert_eq!(
+        date_range("FC3016").unwrap_err(),
+        Error::HLSFormat("SCTE35-OUT must be hexadecimal, found FC3016".to_string())
+    );
+    assert_eq!(
+        date_range("0xFC30160000").unwrap_err(),
+        Error::HLSFormat(
+            "Invalid SCTE35-OUT: SCTE-35 splice_info_section is truncated".to_string()
+        )
+    );
+    assert_eq!(
+        date_range("0x00301600000000000000FFF000").unwrap_err(),
+        Error::HLSFormat(
+            "Invalid SCTE35-OUT: SCTE-35 table_id must be 0xFC, found 0x00".to_string()
+        )
+    );
+}
+
+fn located(tags: Vec<(String, HashMap<String, String>)>) -> Vec<Tag> {
+    tags.into_iter()
+        .enumerate()
+        .map(|(index, (name, attributes))| Tag {
+            location: Location {
+                line: index + 1,
+                column: 1,
+                snippet: format!("#{}", name),
+            },
+            raw_value: attributes.get("VALUE").cloned(),
+            substitutable: HashSet::new(),
+            name,
+            attributes,
+        })
+        .collect()
+}
+
+fn given_a_parsed_segment() -> (String, HashMap<String, String>) {
+    let mut segment_attributes = HashMap::new();
+    segment_attributes.insert("DURATION".to_string(), "9.009".to_string());
+    segment_attributes.insert("TITLE".to_string(), "Intro".to_string());
+    segment_attributes.insert("URI".to_string(), "segments/0.ts".to_string());
+
+    ("EXTINF".to_string(), segment_attributes)
+}
+
+fn given_a_parsed_value_tag(name: &str, value: &str) -> (String, HashMap<String, String>) {
+    let mut attributes = HashMap::new();
+    attributes.insert("VALUE".to_string(), value.to_string());
+
+    (name.to_string(), attributes)
+}
+
+fn given_parsed_media_playlist_tags() -> Vec<(String, HashMap<String, String>)> {
+    vec![
+        ("EXTM3U".to_string(), HashMap::new()),
+        given_a_parsed_value_tag("EXT-X-TARGETDURATION", "10"),
+        given_a_parsed_value_tag("EXT-X-MEDIA-SEQUENCE", "7"),
+        given_a_parsed_value_tag("EXT-X-PLAYLIST-TYPE", "VOD"),
+        given_a_parsed_segment(),
+        ("EXT-X-ENDLIST".to_string(), HashMap::new()),
+    ]
+}
+
 fn given_a_parsed_media_tag() -> (String, HashMap<String, String>) {
     let mut media_attributes = HashMap::new();
     media_attributes.insert("TYPE".to_string(), "AUDIO".to_string());
@@ -120,16 +1156,36 @@ impl VariantStream {
         Self {
             uri: "hdr10/unenc/1650k/vod.m3u8".to_string(),
             bandwidth: 2483789,
-            average_bandwidth: 1762745,
-            codecs: "mp4a.40.2,hvc1.2.4.L90.90".to_string(),
-            resolution: Resolution {
+            average_bandwidth: Some(1762745),
+            codecs: Some("mp4a.40.2,hvc1.2.4.L90.90".into()),
+            resolution: Some(Resolution {
                 width: 11960,
                 height: 55540,
-            },
-            video_range: VideoRange::PQ,
-            frame_rate: "23.97".to_string(),
-            audio: "aac-128k".to_string(),
-            closed_captions: "NONE".to_string(),
+            }),
+            video_range: Some(VideoRange::PQ),
+            frame_rate: Some(FrameRate::from_millis(23970)),
+            audio: Some("aac-128k".to_string()),
+            subtitles: None,
+            closed_captions: Some("NONE".to_string()),
+            hdcp_level: None,
+            video: None,
+            score: None,
+            supplemental_codecs: None,
+            allowed_cpc: None,
+            stable_variant_id: None,
+            pathway_id: None,
+            req_video_layout: None,
+            line: SourceLine::default(),
+        }
+    }
+}
+
+impl VariantStream {
+    fn with_bandwidth_and_audio(bandwidth: u32, audio: &str) -> Self {
+        Self {
+            bandwidth,
+            audio: Some(audio.to_string()),
+            ..Self::example()
         }
     }
 }
@@ -140,11 +1196,20 @@ impl MediaTag {
             media_type: MediaType::Audio,
             group_id: "aac-64k".to_string(),
             name: "English".to_string(),
-            language: "en".to_string(),
-            default: "YES".to_string(),
-            autoselect: "YES".to_string(),
-            channels: "2".to_string(),
-            uri: "audio/unenc/aac_64k/vod.m3u8".to_string(),
+            language: Some("en".to_string()),
+            default: Some(true),
+            autoselect: Some(true),
+            forced: None,
+            assoc_language: None,
+            stable_rendition_id: None,
+            instream_id: None,
+            characteristics: None,
+            channels: Some(Channels {
+                count: 2,
+                ..Channels::default()
+            }),
+            uri: Some("audio/unenc/aac_64k/vod.m3u8".to_string()),
+            line: SourceLine::default(),
         }
     }
 }
@@ -153,13 +1218,36 @@ impl IFrame {
     fn example() -> Self {
         Self {
             bandwidth: 77758,
-            codecs: "hvc1.2.4.L63.90".to_string(),
-            resolution: Resolution {
+            codecs: Some("hvc1.2.4.L63.90".into()),
+            resolution: Some(Resolution {
                 width: 640,
                 height: 360,
-            },
-            video_range: VideoRange::PQ,
+            }),
+            video_range: Some(VideoRange::PQ),
+            hdcp_level: None,
+            video: None,
+            score: None,
+            supplemental_codecs: None,
+            allowed_cpc: None,
+            stable_variant_id: None,
+            pathway_id: None,
+            req_video_layout: None,
             uri: "hdr10/unenc/900k/vod-iframe.m3u8".to_string(),
+            line: SourceLine::default(),
+        }
+    }
+}
+
+impl Segment {
+    fn example() -> Self {
+        Self {
+            duration: 9.009,
+            title: Some("Intro".to_string()),
+            uri: "segments/0.ts".to_string(),
+            keys: vec![],
+            map: None,
+            byte_range: None,
+            parts: vec![],
         }
     }
 }
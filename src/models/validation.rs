@@ -0,0 +1,377 @@
+use crate::models::{
+    IFrame, MasterPlaylist, MediaPlaylist, MediaTag, MediaType, Part, SessionData, VariantStream,
+    VideoRange,
+};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// A rule from the [HLS RFC](https://tools.ietf.org/html/rfc8216) that a playlist can break.
+pub enum Rule {
+    /// The first line of every playlist must be the EXTM3U tag (section 4.3.1.1).
+    ExtM3uFirst,
+    /// AUDIO, VIDEO, SUBTITLES and CLOSED-CAPTIONS on a variant stream must match the GROUP-ID of a
+    /// media tag with the same TYPE (section 4.3.4.2).
+    GroupReference,
+    /// Media tags with `TYPE=CLOSED-CAPTIONS` must not have a URI (section 4.3.4.1).
+    ClosedCaptionsUri,
+    /// Closed captions renditions must have an INSTREAM-ID and other renditions must not
+    /// (section 4.3.4.1). Parsing already enforces this, so only media tags built in code break it.
+    InstreamId,
+    /// At most one media tag in each group may have `DEFAULT=YES` (section 4.3.4.1.1).
+    SingleDefault,
+    /// AUTOSELECT, if present, must be YES when DEFAULT is YES (section 4.3.4.1).
+    AutoselectWithDefault,
+    /// The average bandwidth of a variant stream can't be higher than its peak (section 4.3.4.2).
+    AverageBandwidth,
+    /// EXT-X-SESSION-DATA must have either a VALUE or a URI, but not both (section 4.3.4.4).
+    SessionDataValue,
+    /// Every partial segment must be no longer than the PART-TARGET of EXT-X-PART-INF, which
+    /// playlists with partial segments must have (rfc8216bis section 4.4.3.7).
+    PartTarget,
+    /// Servers that produce partial segments must support blocking playlist reload, so they
+    /// must say `CAN-BLOCK-RELOAD=YES` (rfc8216bis section 6.2.5.2).
+    CanBlockReload,
+    /// Playlists with EXT-X-PART-INF need a PART-HOLD-BACK of at least twice the PART-TARGET
+    /// (rfc8216bis section 4.4.3.8).
+    PartHoldBack,
+    /// An enumerated value this parser doesn't know about, kept because parsing was lenient.
+    UnknownValue,
+}
+
+impl Rule {
+    /// A short, stable identifier for this rule.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::ExtM3uFirst => "extm3u-first",
+            Rule::GroupReference => "group-reference",
+            Rule::ClosedCaptionsUri => "closed-captions-uri",
+            Rule::InstreamId => "instream-id",
+            Rule::SingleDefault => "single-default",
+            Rule::AutoselectWithDefault => "autoselect-with-default",
+            Rule::AverageBandwidth => "average-bandwidth",
+            Rule::SessionDataValue => "session-data-value",
+            Rule::PartTarget => "part-target",
+            Rule::CanBlockReload => "can-block-reload",
+            Rule::PartHoldBack => "part-hold-back",
+            Rule::UnknownValue => "unknown-value",
+        }
+    }
+
+    /// How serious it is to break this rule.
+    pub fn severity(&self) -> Severity {
+        match self {
+            // The RFC only defines the two values, it doesn't spell out a MUST between them
+            Rule::AverageBandwidth => Severity::Warning,
+            // Newer revisions of the spec may have added the value
+            Rule::UnknownValue => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Severity {
+    /// Clients may still be able to play the playlist.
+    Warning,
+    /// The playlist breaks a MUST in the spec.
+    Error,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The part of the playlist a diagnostic is about.
+pub enum Entry<'a> {
+    Playlist,
+    VariantStream(&'a VariantStream),
+    MediaTag(&'a MediaTag),
+    IFrame(&'a IFrame),
+    SessionData(&'a SessionData),
+    Part(&'a Part),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// A single rule broken by a playlist.
+pub struct Diagnostic<'a> {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub entry: Entry<'a>,
+    /// A human readable description of the problem.
+    pub message: String,
+}
+
+impl<'a> Diagnostic<'a> {
+    fn new(rule: Rule, entry: Entry<'a>, message: String) -> Self {
+        Self {
+            rule,
+            severity: rule.severity(),
+            entry,
+            message,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.rule.id(), self.message)
+    }
+}
+
+/// Checks a playlist against the rules of the spec, returning every rule it breaks.
+/// An empty list means the playlist is valid.
+pub fn validate(playlist: &MasterPlaylist) -> Vec<Diagnostic<'_>> {
+    let mut diagnostics = vec![];
+
+    if !playlist.starts_with_extm3u {
+        diagnostics.push(Diagnostic::new(
+            Rule::ExtM3uFirst,
+            Entry::Playlist,
+            "Playlist does not start with #EXTM3U".to_string(),
+        ));
+    }
+
+    for variant_stream in &playlist.variant_streams {
+        validate_variant_stream(playlist, variant_stream, &mut diagnostics);
+    }
+
+    for i_frame in &playlist.i_frames {
+        if let Some(VideoRange::Other(video_range)) = &i_frame.video_range {
+            diagnostics.push(unknown_video_range(
+                Entry::IFrame(i_frame),
+                &i_frame.uri,
+                video_range,
+            ));
+        }
+    }
+
+    for session_data in &playlist.session_data {
+        let problem = match (&session_data.value, &session_data.uri) {
+            (Some(_), Some(_)) => "has both a VALUE and a URI",
+            (None, None) => "has neither a VALUE nor a URI",
+            _ => continue,
+        };
+        diagnostics.push(Diagnostic::new(
+            Rule::SessionDataValue,
+            Entry::SessionData(session_data),
+            format!("Session data {:?} {}", session_data.data_id, problem),
+        ));
+    }
+
+    let mut defaults = HashMap::new();
+    for media_tag in &playlist.media_tags {
+        let entry = Entry::MediaTag(media_tag);
+        if media_tag.media_type == MediaType::ClosedCaptions && media_tag.uri.is_some() {
+            diagnostics.push(Diagnostic::new(
+                Rule::ClosedCaptionsUri,
+                entry,
+                format!(
+                    "CLOSED-CAPTIONS rendition {:?} in group {:?} must not have a URI",
+                    media_tag.name, media_tag.group_id
+                ),
+            ));
+        }
+
+        let is_closed_captions = media_tag.media_type == MediaType::ClosedCaptions;
+        if is_closed_captions != media_tag.instream_id.is_some() {
+            let problem = if is_closed_captions {
+                "is missing its INSTREAM-ID"
+            } else {
+                "must not have an INSTREAM-ID"
+            };
+            diagnostics.push(Diagnostic::new(
+                Rule::InstreamId,
+                entry,
+                format!(
+                    "{} rendition {:?} in group {:?} {}",
+                    media_tag.media_type, media_tag.name, media_tag.group_id, problem
+                ),
+            ));
+        }
+
+        if media_tag.default() && media_tag.autoselect == Some(false) {
+            diagnostics.push(Diagnostic::new(
+                Rule::AutoselectWithDefault,
+                entry,
+                format!(
+                    "Rendition {:?} in group {:?} has DEFAULT=YES but AUTOSELECT=NO",
+                    media_tag.name, media_tag.group_id
+                ),
+            ));
+        }
+
+        if media_tag.default() {
+            let group = (media_tag.media_type, media_tag.group_id.as_str());
+            let count = defaults.entry(group).or_insert(0);
+            *count += 1;
+            if *count == 2 {
+                diagnostics.push(Diagnostic::new(
+                    Rule::SingleDefault,
+                    entry,
+                    format!(
+                        "{} group {:?} has more than one rendition with DEFAULT=YES",
+                        media_tag.media_type, media_tag.group_id
+                    ),
+                ));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn validate_variant_stream<'a>(
+    playlist: &MasterPlaylist,
+    variant_stream: &'a VariantStream,
+    diagnostics: &mut Vec<Diagnostic<'a>>,
+) {
+    let entry = Entry::VariantStream(variant_stream);
+    let group_references = [
+        (MediaType::Audio, variant_stream.audio.as_deref()),
+        (MediaType::Video, variant_stream.video.as_deref()),
+        (MediaType::Subtitles, variant_stream.subtitles.as_deref()),
+        (
+            MediaType::ClosedCaptions,
+            // NONE means there are no closed captions in any rendition of this variant stream
+            variant_stream
+                .closed_captions
+                .as_deref()
+                .filter(|closed_captions| *closed_captions != "NONE"),
+        ),
+    ];
+
+    for (media_type, group_id) in group_references.iter() {
+        if let Some(group_id) = group_id {
+            let has_group = playlist.media_tags.iter().any(|media_tag| {
+                media_tag.media_type == *media_type && media_tag.group_id == *group_id
+            });
+            if !has_group {
+                diagnostics.push(Diagnostic::new(
+                    Rule::GroupReference,
+                    entry,
+                    format!(
+                        "Variant stream {} refers to {} group {:?} but there is no such EXT-X-MEDIA",
+                        variant_stream.uri, media_type, group_id
+                    ),
+                ));
+            }
+        }
+    }
+
+    if let Some(VideoRange::Other(video_range)) = &variant_stream.video_range {
+        diagnostics.push(unknown_video_range(entry, &variant_stream.uri, video_range));
+    }
+
+    if let Some(average_bandwidth) = variant_stream.average_bandwidth {
+        if average_bandwidth > variant_stream.bandwidth {
+            diagnostics.push(Diagnostic::new(
+                Rule::AverageBandwidth,
+                entry,
+                format!(
+                    "Variant stream {} has AVERAGE-BANDWIDTH {} above its BANDWIDTH {}",
+                    variant_stream.uri, average_bandwidth, variant_stream.bandwidth
+                ),
+            ));
+        }
+    }
+}
+
+fn unknown_video_range<'a>(entry: Entry<'a>, uri: &str, video_range: &str) -> Diagnostic<'a> {
+    Diagnostic::new(
+        Rule::UnknownValue,
+        entry,
+        format!("{} has an unknown VIDEO-RANGE {}", uri, video_range),
+    )
+}
+
+/// Checks a media playlist against the rules of the spec that apply to low-latency playlists,
+/// returning every rule it breaks.
+pub fn validate_media_playlist(playlist: &MediaPlaylist) -> Vec<Diagnostic<'_>> {
+    let mut diagnostics = vec![];
+    let parts: Vec<&Part> = playlist
+        .segments
+        .iter()
+        .flat_map(|segment| segment.parts())
+        .chain(&playlist.parts)
+        .collect();
+
+    match playlist.part_target {
+        Some(part_target) => {
+            for part in &parts {
+                if part.duration() > part_target {
+                    diagnostics.push(Diagnostic::new(
+                        Rule::PartTarget,
+                        Entry::Part(part),
+                        format!(
+                            "Part {} lasts {} seconds, longer than the PART-TARGET of {}",
+                            part.uri(),
+                            part.duration(),
+                            part_target
+                        ),
+                    ));
+                }
+            }
+        }
+        None if !parts.is_empty() => diagnostics.push(Diagnostic::new(
+            Rule::PartTarget,
+            Entry::Playlist,
+            "Playlist has EXT-X-PART tags but no EXT-X-PART-INF".to_string(),
+        )),
+        None => {}
+    }
+
+    let server_control = playlist.server_control.unwrap_or_default();
+    if (playlist.part_target.is_some() || !parts.is_empty())
+        && server_control.can_block_reload != Some(true)
+    {
+        diagnostics.push(Diagnostic::new(
+            Rule::CanBlockReload,
+            Entry::Playlist,
+            "Playlist has partial segments but EXT-X-SERVER-CONTROL doesn't have CAN-BLOCK-RELOAD=YES"
+                .to_string(),
+        ));
+    }
+
+    if let Some(part_target) = playlist.part_target {
+        let message = match server_control.part_hold_back {
+            None => Some("Playlist has EXT-X-PART-INF but no PART-HOLD-BACK".to_string()),
+            Some(part_hold_back) if part_hold_back < 2.0 * part_target => Some(format!(
+                "PART-HOLD-BACK of {} is less than twice the PART-TARGET of {}",
+                part_hold_back, part_target
+            )),
+            Some(_) => None,
+        };
+        if let Some(message) = message {
+            diagnostics.push(Diagnostic::new(
+                Rule::PartHoldBack,
+                Entry::Playlist,
+                message,
+            ));
+        }
+    }
+
+    // Lenient parsing keeps these tags as unknown tags when it doesn't know one of their values
+    for unknown_tag in &playlist.unknown_tags {
+        if ["EXT-X-PLAYLIST-TYPE", "EXT-X-KEY", "EXT-X-PRELOAD-HINT"]
+            .contains(&unknown_tag.name.as_str())
+        {
+            diagnostics.push(Diagnostic::new(
+                Rule::UnknownValue,
+                Entry::Playlist,
+                format!(
+                    "#{} on line {} has a value this parser doesn't know about",
+                    unknown_tag.name, unknown_tag.line
+                ),
+            ));
+        }
+    }
+
+    diagnostics
+}
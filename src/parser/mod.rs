@@ -1,22 +1,64 @@
 mod nom_parser;
+mod variables;
 
-use crate::errors::{Error, Result};
-use nom_parser::master_playlist;
-use std::collections::HashMap;
+use crate::errors::{Error, Location, Result};
+use nom_parser::{playlist, quoted_attribute_names};
+use std::collections::{HashMap, HashSet};
 
-impl From<nom::Err<nom::error::Error<&str>>> for Error {
-    fn from(e: nom::Err<nom::error::Error<&str>>) -> Self {
-        Error::Parse(e.to_string())
-    }
+pub(crate) use variables::{substitute_variables, VariableSources};
+
+/// A tag along with where it was found in the source playlist.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Tag {
+    pub(crate) name: String,
+    pub(crate) attributes: HashMap<String, String>,
+    /// The raw text following the colon on the tag's line, if there was any.
+    pub(crate) raw_value: Option<String>,
+    /// The attributes that variable references can be substituted into: those written as quoted
+    /// strings, and the URI of a tag followed by a URI line.
+    pub(crate) substitutable: HashSet<String>,
+    pub(crate) location: Location,
 }
 
 pub(crate) struct HLSParser {}
 
 impl HLSParser {
     /// Takes a string representing an HLS file and parses it into a list of Tags
-    pub(crate) fn parse(&self, hls_str: &str) -> Result<Vec<(String, HashMap<String, String>)>> {
-        let (_, res) = master_playlist(hls_str)?;
+    pub(crate) fn parse(&self, hls_str: &str) -> Result<Vec<Tag>> {
+        let (_, res) = playlist(hls_str).map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => Error::Parse {
+                message: format!("Could not parse line ({:?})", e.code),
+                location: Location::new(hls_str, e.input),
+            },
+            nom::Err::Incomplete(_) => Error::Parse {
+                message: "Unexpected end of playlist".to_string(),
+                location: Location::new(hls_str, ""),
+            },
+        })?;
 
-        Ok(res)
+        Ok(res
+            .into_iter()
+            .map(|(position, (name, attributes))| {
+                let raw_value = position
+                    .lines()
+                    .next()
+                    .and_then(|line| line.split_once(':'))
+                    .map(|(_, value)| value.to_string());
+                let mut substitutable = raw_value
+                    .as_deref()
+                    .map(quoted_attribute_names)
+                    .unwrap_or_default();
+                if name == "EXTINF" || name == "EXT-X-STREAM-INF" {
+                    substitutable.insert("URI".to_string());
+                }
+                Tag {
+                    name,
+                    attributes,
+                    raw_value,
+                    substitutable,
+                    location: Location::new(hls_str, position),
+                }
+            })
+            .collect())
     }
 }
@@ -1,39 +1,84 @@
-use nom::character::complete::multispace0;
-use nom::combinator::{all_consuming, map, verify};
-use nom::multi::{fold_many0, many1, separated_list1};
+use nom::character::complete::{line_ending, multispace0, space0};
+use nom::combinator::{all_consuming, eof, map, opt, verify};
+use nom::multi::{fold_many0, fold_many1, many0, separated_list1};
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 
 use nom::branch::alt;
-use nom::bytes::complete::{is_not, tag, take_until};
+use nom::bytes::complete::{is_not, tag, take_while1};
 use nom::IResult;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// A tag name paired with its attributes, as produced by the parser.
+pub(crate) type RawTag = (String, HashMap<String, String>);
+
+/// A parsed tag paired with the input it started at, so the caller can work out where
+/// in the playlist it was found.
+pub(crate) type LocatedTag<'a> = (&'a str, RawTag);
+
+fn located<'a, O>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (&'a str, O)> {
+    move |input: &'a str| {
+        let (rest, output) = parser(input)?;
+        Ok((rest, (input, output)))
+    }
+}
+
+/// Consumes the end of the current line along with any blank lines that follow it.
+fn end_of_line(line_str: &str) -> nom::IResult<&str, &str> {
+    terminated(alt((line_ending, eof)), multispace0)(line_str)
+}
 
 fn value(attr_str: &str) -> nom::IResult<&str, String> {
-    map(terminated(is_not(",\n"), multispace0), |attr: &str| {
+    map(terminated(is_not(",\r\n"), space0), |attr: &str| {
         attr.to_string()
     })(attr_str)
 }
 
 fn quoted_value(attr_str: &str) -> nom::IResult<&str, String> {
     map(
-        terminated(delimited(tag("\""), is_not("\""), tag("\"")), multispace0),
-        |attr: &str| attr.to_string(),
+        terminated(delimited(tag("\""), opt(is_not("\"")), tag("\"")), space0),
+        |attr: Option<&str>| attr.unwrap_or_default().to_string(),
     )(attr_str)
 }
 
+fn is_attribute_name_char(c: char) -> bool {
+    c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-'
+}
+
+fn attribute_name(name_str: &str) -> nom::IResult<&str, &str> {
+    take_while1(is_attribute_name_char)(name_str)
+}
+
 fn attribute_pair(attr_str: &str) -> nom::IResult<&str, (String, String)> {
     map(
-        separated_pair(take_until("="), tag("="), alt((quoted_value, value))),
-        |(key, value)| (key.to_string(), value.to_string()),
+        separated_pair(attribute_name, tag("="), alt((quoted_value, value))),
+        |(key, value)| (key.to_string(), value),
     )(attr_str)
 }
 
+/// The names of the attributes in an attribute list, without the leading colon, whose values are
+/// quoted strings.
+pub(crate) fn quoted_attribute_names(attrs_str: &str) -> HashSet<String> {
+    let quoting = separated_pair(
+        attribute_name,
+        tag("="),
+        alt((map(quoted_value, |_| true), map(value, |_| false))),
+    );
+    separated_list1(tag(","), quoting)(attrs_str)
+        .map(|(_, attrs)| {
+            attrs
+                .into_iter()
+                .filter(|(_, quoted)| *quoted)
+                .map(|(name, _)| name.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn attributes(attrs_str: &str) -> nom::IResult<&str, HashMap<String, String>> {
     fold_many0(
-        preceded(
-            tag(":"),
-            terminated(separated_list1(tag(","), attribute_pair), multispace0),
-        ),
+        preceded(tag(":"), separated_list1(tag(","), attribute_pair)),
         HashMap::new(),
         |mut map, attrs| {
             for (key, value) in attrs {
@@ -44,43 +89,113 @@ fn attributes(attrs_str: &str) -> nom::IResult<&str, HashMap<String, String>> {
     )(attrs_str)
 }
 
+fn is_attribute_list(value_str: &str) -> bool {
+    let name_len = value_str
+        .find(|c| !is_attribute_name_char(c))
+        .unwrap_or(value_str.len());
+    name_len > 0 && value_str[name_len..].starts_with('=')
+}
+
+/// Parses the value of a tag that carries a single value rather than an attribute list,
+/// e.g. `#EXT-X-TARGETDURATION:10`. The value is stored under the `VALUE` key.
+fn single_value(value_str: &str) -> nom::IResult<&str, HashMap<String, String>> {
+    map(
+        verify(preceded(tag(":"), is_not("\r\n")), |value: &str| {
+            !is_attribute_list(value)
+        }),
+        |value: &str| {
+            let mut attrs = HashMap::new();
+            attrs.insert("VALUE".to_string(), value.to_string());
+            attrs
+        },
+    )(value_str)
+}
+
 fn tag_name(tag_str: &str) -> nom::IResult<&str, String> {
-    map(preceded(tag("#"), is_not(":\n")), |name: &str| {
+    map(preceded(tag("#"), is_not(":\r\n")), |name: &str| {
         name.to_string()
     })(tag_str)
 }
 
-fn tag_and_attributes(tag_str: &str) -> nom::IResult<&str, (String, HashMap<String, String>)> {
-    pair(tag_name, attributes)(tag_str)
+// Note: I did try to get this to return an Option<HashMap> in the case of tags with no
+// attributes but I couldn't get the parser to play nice in the time that I had.
+// An optional hashmap here would be better than returning an empty hashmap if the tag
+// has no associated attributes.
+fn tag_and_attributes(tag_str: &str) -> nom::IResult<&str, RawTag> {
+    terminated(pair(tag_name, alt((single_value, attributes))), end_of_line)(tag_str)
 }
 
 fn uri(uri_str: &str) -> nom::IResult<&str, String> {
-    verify(value, |uri: &str| !uri.starts_with("#"))(uri_str)
+    map(
+        verify(terminated(is_not("\r\n"), end_of_line), |uri: &str| {
+            !uri.starts_with('#')
+        }),
+        |uri: &str| uri.to_string(),
+    )(uri_str)
+}
+
+fn variant_stream(variant_stream_str: &str) -> nom::IResult<&str, RawTag> {
+    map(pair(tag_and_attributes, uri), |((tag, mut attrs), uri)| {
+        attrs.insert("URI".to_string(), uri);
+        (tag, attrs)
+    })(variant_stream_str)
 }
 
-fn variant_stream(
-    variant_stream_str: &str,
-) -> nom::IResult<&str, (String, HashMap<String, String>)> {
+/// Parses the `<duration>,[<title>]` value of an EXTINF tag into `DURATION` and `TITLE`.
+fn extinf(extinf_str: &str) -> nom::IResult<&str, RawTag> {
     map(
-        tuple((tag_name, attributes, preceded(multispace0, uri))),
-        |(tag, mut attrs, uri)| {
+        preceded(
+            tag("#EXTINF:"),
+            terminated(
+                pair(
+                    is_not(",\r\n"),
+                    opt(preceded(tag(","), opt(is_not("\r\n")))),
+                ),
+                end_of_line,
+            ),
+        ),
+        |(duration, title): (&str, Option<Option<&str>>)| {
+            let mut attrs = HashMap::new();
+            attrs.insert("DURATION".to_string(), duration.to_string());
+            if let Some(title) = title.flatten() {
+                attrs.insert("TITLE".to_string(), title.to_string());
+            }
+            ("EXTINF".to_string(), attrs)
+        },
+    )(extinf_str)
+}
+
+/// Parses a media segment: an EXTINF tag, any tags applying to the segment, and its URI.
+/// The tags found between the EXTINF and the URI are returned first so that they precede
+/// the segment they apply to, and the URI is stored on the EXTINF tag.
+fn segment(segment_str: &str) -> nom::IResult<&str, Vec<LocatedTag<'_>>> {
+    map(
+        tuple((located(extinf), many0(located(tag_and_attributes)), uri)),
+        |((position, (name, mut attrs)), mut tags, uri)| {
             attrs.insert("URI".to_string(), uri);
-            (tag, attrs)
+            tags.push((position, (name, attrs)));
+            tags
         },
-    )(variant_stream_str)
+    )(segment_str)
 }
 
-// Note: I did try to get this to return an Option<HashMap> in the case of tags with no
-// attributes but I couldn't get the parser to play nice in the time that I had.
-// An optional hashmap here would be better than returning an empty hashmap if the tag
-// has no associated attributes.
-pub(crate) fn master_playlist(
-    playlist_str: &str,
-) -> IResult<&str, Vec<(String, HashMap<String, String>)>> {
-    all_consuming(many1(terminated(
-        alt((variant_stream, tag_and_attributes)),
-        multispace0,
-    )))(playlist_str)
+/// Parses either a master or a media playlist into its list of tags.
+pub(crate) fn playlist(playlist_str: &str) -> IResult<&str, Vec<LocatedTag<'_>>> {
+    all_consuming(fold_many1(
+        terminated(
+            alt((
+                segment,
+                map(located(variant_stream), |tag| vec![tag]),
+                map(located(tag_and_attributes), |tag| vec![tag]),
+            )),
+            multispace0,
+        ),
+        Vec::new(),
+        |mut tags, parsed| {
+            tags.extend(parsed);
+            tags
+        },
+    ))(playlist_str)
 }
 
 #[cfg(test)]
@@ -135,11 +250,15 @@ mod tests {
         expected
     }
 
+    fn without_locations(tags: Vec<LocatedTag<'_>>) -> Vec<RawTag> {
+        tags.into_iter().map(|(_, tag)| tag).collect()
+    }
+
     #[test]
     fn parses_all_tags() {
         let tags_str = "#EXTM3U\n#EXT-X-INDEPENDENT-SEGMENTS\n\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-64k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_64k/vod.m3u8\"\n\n#EXT-X-STREAM-INF:BANDWIDTH=2312764,AVERAGE-BANDWIDTH=1919803,CODECS=\"ec-3,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/900k/vod.m3u8\n\n#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/900k/vod-iframe.m3u8\"\n\n";
 
-        let parsed = master_playlist(tags_str);
+        let parsed = playlist(tags_str).map(|(rest, tags)| (rest, without_locations(tags)));
 
         let expected = vec![
             ("EXTM3U".to_string(), HashMap::new()),
@@ -223,6 +342,17 @@ mod tests {
         )
     }
 
+    #[test]
+    fn parses_attribute_with_empty_quoted_value() {
+        let attr_str = ":NAME=\"\",AUDIO=\"atmos\"";
+
+        let parsed = attributes(attr_str).unwrap().1;
+
+        assert_eq!(parsed["NAME"], "");
+        assert_eq!(parsed["AUDIO"], "atmos");
+        assert!(quoted_attribute_names(&attr_str[1..]).contains("NAME"));
+    }
+
     #[test]
     fn parses_attribute_with_list_value_into_key_value() {
         let attr_str = "CODECS=\"ec-3,hvc1.2.4.L63.90\"";
@@ -252,4 +382,82 @@ mod tests {
             Ok(("", ("RESOLUTION".to_string(), "640x360".to_string())))
         )
     }
+
+    #[test]
+    fn parses_attribute_with_hex_sequence_value_into_key_value() {
+        let attrs_str = ":METHOD=AES-128,IV=0x9c7db8778570d05c3177c349fd9236aa,URI=\"key.bin\"";
+
+        let (_, attrs) = attributes(attrs_str).unwrap();
+
+        assert_eq!(
+            attrs.get("IV"),
+            Some(&"0x9c7db8778570d05c3177c349fd9236aa".to_string())
+        );
+        assert_eq!(attrs.get("URI"), Some(&"key.bin".to_string()));
+    }
+
+    #[test]
+    fn parses_single_value_tag() {
+        let tag_str = "#EXT-X-TARGETDURATION:10\n";
+
+        let mut expected = HashMap::new();
+        expected.insert("VALUE".to_string(), "10".to_string());
+
+        assert_eq!(
+            tag_and_attributes(tag_str),
+            Ok(("", ("EXT-X-TARGETDURATION".to_string(), expected)))
+        )
+    }
+
+    #[test]
+    fn parses_segment_with_intermediate_tags() {
+        let segment_str = "#EXTINF:9.009,Intro\n#EXT-X-DISCONTINUITY\nsegments/0.ts\n";
+
+        let mut extinf_attrs = HashMap::new();
+        extinf_attrs.insert("DURATION".to_string(), "9.009".to_string());
+        extinf_attrs.insert("TITLE".to_string(), "Intro".to_string());
+        extinf_attrs.insert("URI".to_string(), "segments/0.ts".to_string());
+
+        assert_eq!(
+            segment(segment_str).map(|(rest, tags)| (rest, without_locations(tags))),
+            Ok((
+                "",
+                vec![
+                    ("EXT-X-DISCONTINUITY".to_string(), HashMap::new()),
+                    ("EXTINF".to_string(), extinf_attrs),
+                ]
+            ))
+        )
+    }
+
+    #[test]
+    fn parses_media_playlist() {
+        let playlist_str = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:9.009,\nfirst.ts\n#EXTINF:3.003,\nsecond.ts\n#EXT-X-ENDLIST\n";
+
+        let parsed = without_locations(playlist(playlist_str).unwrap().1);
+        let names: Vec<&str> = parsed.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "EXTM3U",
+                "EXT-X-TARGETDURATION",
+                "EXTINF",
+                "EXTINF",
+                "EXT-X-ENDLIST"
+            ]
+        );
+        assert_eq!(parsed[3].1.get("URI"), Some(&"second.ts".to_string()));
+        assert_eq!(parsed[3].1.get("TITLE"), None);
+    }
+
+    #[test]
+    fn locates_tags_at_their_starting_input() {
+        let playlist_str = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n";
+
+        let (_, parsed) = playlist(playlist_str).unwrap();
+
+        assert_eq!(parsed[0].0, playlist_str);
+        assert_eq!(parsed[1].0, "#EXT-X-TARGETDURATION:10\n");
+    }
 }
@@ -0,0 +1,298 @@
+use crate::errors::{Error, Result};
+use crate::parser::Tag;
+use reqwest::Url;
+use std::collections::{BTreeMap, HashMap};
+
+/// Where EXT-X-DEFINE tags that don't give a VALUE get their value from.
+pub(crate) struct VariableSources<'a> {
+    /// Variables of the master playlist, for IMPORT.
+    pub(crate) imports: &'a BTreeMap<String, String>,
+    /// The URL the playlist was loaded from, for QUERYPARAM.
+    pub(crate) url: Option<&'a str>,
+    /// Whether the tags are of a media playlist, as only those can IMPORT variables.
+    pub(crate) media_playlist: bool,
+}
+
+/// Resolves every EXT-X-DEFINE tag and replaces `{$name}` references in the tags that follow it.
+/// As the spec allows, references are only replaced in URI lines and quoted-string attribute
+/// values. The raw value of each tag, which is what unknown tags are written back from, has its
+/// quoted strings substituted in the same way. Each EXT-X-DEFINE is left with a NAME and the
+/// resolved VALUE, whichever form it was written in, and a QUERYPARAM define keeps its QUERYPARAM
+/// so that it can be written back in the same form.
+pub(crate) fn substitute_variables(tags: &mut [Tag], sources: &VariableSources) -> Result<()> {
+    let mut variables = BTreeMap::new();
+
+    for tag in tags.iter_mut() {
+        resolve_tag(tag, &mut variables, sources)
+            .map_err(|e| e.in_tag(&tag.name, &tag.location))?;
+    }
+
+    Ok(())
+}
+
+fn resolve_tag(
+    tag: &mut Tag,
+    variables: &mut BTreeMap<String, String>,
+    sources: &VariableSources,
+) -> Result<()> {
+    if tag.name == "EXT-X-DEFINE" {
+        let (name, value) = define(&tag.attributes, sources)?;
+        if variables.contains_key(&name) {
+            return Err(Error::HLSFormat(format!(
+                "Variable {} is defined more than once",
+                name
+            )));
+        }
+        let query_param = tag.attributes.remove("QUERYPARAM");
+        tag.attributes.clear();
+        if let Some(query_param) = query_param {
+            tag.attributes.insert("QUERYPARAM".to_string(), query_param);
+        }
+        tag.attributes.insert("NAME".to_string(), name.clone());
+        tag.attributes.insert("VALUE".to_string(), value.clone());
+        variables.insert(name, value);
+    } else {
+        for (name, value) in tag.attributes.iter_mut() {
+            if tag.substitutable.contains(name) && value.contains("{$") {
+                *value = substitute(value, variables)?;
+            }
+        }
+        if let Some(raw_value) = tag.raw_value.as_mut().filter(|raw| raw.contains("{$")) {
+            *raw_value = substitute_quoted(raw_value, variables)?;
+        }
+    }
+    Ok(())
+}
+
+/// Works out the name and value of a single EXT-X-DEFINE tag.
+fn define(
+    attributes: &HashMap<String, String>,
+    sources: &VariableSources,
+) -> Result<(String, String)> {
+    if let Some(name) = attributes.get("NAME") {
+        let value = attributes
+            .get("VALUE")
+            .ok_or(Error::HLSFormat(format!("Variable {} has no VALUE", name)))?;
+        Ok((name.clone(), value.clone()))
+    } else if let Some(name) = attributes.get("IMPORT") {
+        if !sources.media_playlist {
+            return Err(Error::HLSFormat(format!(
+                "Variable {} can't be imported, only media playlists can IMPORT",
+                name
+            )));
+        }
+        let value = sources.imports.get(name).ok_or(Error::HLSFormat(format!(
+            "Variable {} can't be imported, the master playlist doesn't define it",
+            name
+        )))?;
+        Ok((name.clone(), value.clone()))
+    } else if let Some(name) = attributes.get("QUERYPARAM") {
+        let value = sources
+            .url
+            .and_then(|url| Url::parse(url).ok())
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            })
+            .ok_or(Error::HLSFormat(format!(
+                "Variable {} isn't a query parameter of the playlist URL",
+                name
+            )))?;
+        Ok((name.clone(), value))
+    } else {
+        Err(Error::HLSFormat(
+            "EXT-X-DEFINE needs one of NAME, IMPORT or QUERYPARAM".to_string(),
+        ))
+    }
+}
+
+fn is_variable_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Replaces each `{$name}` in `value` with the value of the variable.
+fn substitute(value: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut substituted = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("{$") {
+        substituted.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        match reference.find('}') {
+            Some(end) if end > 0 && reference[..end].chars().all(is_variable_name_char) => {
+                let name = &reference[..end];
+                let variable = variables.get(name).ok_or(Error::HLSFormat(format!(
+                    "Reference to undefined variable {}",
+                    name
+                )))?;
+                substituted.push_str(variable);
+                rest = &reference[end + 1..];
+            }
+            // Not a variable reference, so keep the text as it is
+            _ => {
+                substituted.push_str("{$");
+                rest = reference;
+            }
+        }
+    }
+    substituted.push_str(rest);
+
+    Ok(substituted)
+}
+
+/// Replaces each `{$name}` inside the quoted strings of a raw attribute list, leaving the rest
+/// of it as it is.
+fn substitute_quoted(raw_value: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let pieces = raw_value
+        .split('"')
+        .enumerate()
+        .map(|(index, piece)| {
+            // Every other piece is between a pair of quotes
+            if index % 2 == 1 {
+                substitute(piece, variables)
+            } else {
+                Ok(piece.to_string())
+            }
+        })
+        .collect::<Result<Vec<String>>>()?;
+    Ok(pieces.join("\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::HLSParser;
+
+    fn substituted(playlist_str: &str, sources: &VariableSources) -> Result<Vec<Tag>> {
+        let mut tags = HLSParser {}.parse(playlist_str)?;
+        substitute_variables(&mut tags, sources)?;
+        Ok(tags)
+    }
+
+    #[test]
+    fn substitutes_defined_variables_into_attributes() {
+        let imports = BTreeMap::new();
+        let sources = VariableSources {
+            imports: &imports,
+            url: None,
+            media_playlist: false,
+        };
+
+        let tags = substituted(
+            "#EXTM3U\n#EXT-X-DEFINE:NAME=\"cdn\",VALUE=\"https://cdn.example.com\"\n#EXT-X-DEFINE:NAME=\"audio\",VALUE=\"aac-128k\"\n#EXT-X-STREAM-INF:BANDWIDTH=1280000,AUDIO=\"{$audio}\"\n{$cdn}/low.m3u8\n",
+            &sources,
+        )
+        .unwrap();
+
+        assert_eq!(
+            tags[3].attributes["URI"],
+            "https://cdn.example.com/low.m3u8"
+        );
+        assert_eq!(tags[3].attributes["AUDIO"], "aac-128k");
+    }
+
+    #[test]
+    fn resolves_imports_and_query_parameters() {
+        let mut imports = BTreeMap::new();
+        imports.insert("base".to_string(), "https://cdn.example.com".to_string());
+        let sources = VariableSources {
+            imports: &imports,
+            url: Some("https://example.com/video.m3u8?token=a%2Fb&other=1"),
+            media_playlist: true,
+        };
+
+        let tags = substituted(
+            "#EXTM3U\n#EXT-X-DEFINE:IMPORT=\"base\"\n#EXT-X-DEFINE:QUERYPARAM=\"token\"\n#EXT-X-TARGETDURATION:10\n#EXTINF:9.009,\n{$base}/0.ts?token={$token}\n",
+            &sources,
+        )
+        .unwrap();
+
+        assert_eq!(tags[2].attributes["NAME"], "token");
+        assert_eq!(tags[2].attributes["VALUE"], "a/b");
+        assert_eq!(
+            tags[4].attributes["URI"],
+            "https://cdn.example.com/0.ts?token=a/b"
+        );
+    }
+
+    #[test]
+    fn rejects_undefined_references() {
+        let imports = BTreeMap::new();
+        let sources = VariableSources {
+            imports: &imports,
+            url: None,
+            media_playlist: false,
+        };
+
+        let undefined = substituted(
+            "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=1280000\n{$cdn}/low.m3u8\n",
+            &sources,
+        )
+        .unwrap_err();
+        let not_imported =
+            substituted("#EXTM3U\n#EXT-X-DEFINE:IMPORT=\"base\"\n", &sources).unwrap_err();
+
+        assert_eq!(
+            undefined.to_string(),
+            "Invalid HLS in #EXT-X-STREAM-INF at line 2, column 1 (`#EXT-X-STREAM-INF:BANDWIDTH=1280000`): Reference to undefined variable cdn"
+        );
+        assert!(not_imported
+            .to_string()
+            .contains("Variable base can't be imported"));
+    }
+
+    #[test]
+    fn only_substitutes_quoted_values_and_uri_lines() {
+        let imports = BTreeMap::new();
+        let sources = VariableSources {
+            imports: &imports,
+            url: None,
+            media_playlist: false,
+        };
+
+        let tags = substituted(
+            "#EXTM3U\n#EXT-X-DEFINE:NAME=\"type\",VALUE=\"AUDIO\"\n#EXT-X-MEDIA:TYPE={$type},GROUP-ID=\"{$type}\",NAME=\"English\"\n",
+            &sources,
+        )
+        .unwrap();
+
+        assert_eq!(tags[2].attributes["TYPE"], "{$type}");
+        assert_eq!(tags[2].attributes["GROUP-ID"], "AUDIO");
+    }
+
+    #[test]
+    fn substitutes_quoted_strings_in_raw_values() {
+        let imports = BTreeMap::new();
+        let sources = VariableSources {
+            imports: &imports,
+            url: None,
+            media_playlist: false,
+        };
+
+        let tags = substituted(
+            "#EXTM3U\n#EXT-X-DEFINE:NAME=\"id\",VALUE=\"abc\"\n#EXT-X-VENDOR-AD:ID=\"{$id}\",COUNT={$id}\n",
+            &sources,
+        )
+        .unwrap();
+
+        assert_eq!(tags[2].raw_value.as_deref(), Some("ID=\"abc\",COUNT={$id}"));
+    }
+
+    #[test]
+    fn rejects_imports_in_master_playlists() {
+        let mut imports = BTreeMap::new();
+        imports.insert("base".to_string(), "https://cdn.example.com".to_string());
+        let sources = VariableSources {
+            imports: &imports,
+            url: None,
+            media_playlist: false,
+        };
+
+        let err = substituted("#EXTM3U\n#EXT-X-DEFINE:IMPORT=\"base\"\n", &sources).unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("Variable base can't be imported, only media playlists can IMPORT"));
+    }
+}
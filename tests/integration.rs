@@ -1,16 +1,672 @@
-use hls_parser::models::MasterPlaylist;
-use hls_parser::parse_hls;
-use std::convert::TryInto;
+use hls_parser::models::{
+    validate, validate_media_playlist, BreakDuration, Channels, CodecFormat, HevcProfile,
+    InitializationVector, KeyMethod, Level, MasterPlaylist, MediaTag, MediaType, ParseOptions,
+    Playlist, PreloadHintType, Resolution, SpliceCommand, VideoRange,
+};
+use hls_parser::{
+    parse_hls, parse_hls_from_url, parse_hls_from_url_with_client, parse_hls_with_options,
+    parse_playlist, parse_playlist_with_options, ApiClient, BlockingApiClient, Error,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const MEDIA_HLS_STR: &str = "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-PLAYLIST-TYPE:VOD\n#EXTINF:9.009,\nhttp://media.example.com/first.ts\n#EXTINF:9.009,\nhttp://media.example.com/second.ts\n#EXTINF:3.003,\nhttp://media.example.com/third.ts\n#EXT-X-ENDLIST\n";
 
 const HLS_STR: &str = "#EXTM3U\n#EXT-X-INDEPENDENT-SEGMENTS\n\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-128k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_128k/vod.m3u8\"\n\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac-64k\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"2\",URI=\"audio/unenc/aac_64k/vod.m3u8\"\n\n#EXT-X-STREAM-INF:BANDWIDTH=2483789,AVERAGE-BANDWIDTH=1762745,CODECS=\"mp4a.40.2,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/1650k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=15811232,AVERAGE-BANDWIDTH=10058085,CODECS=\"mp4a.40.2,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/10000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=20655057,AVERAGE-BANDWIDTH=13066616,CODECS=\"mp4a.40.2,hvc1.2.4.H150.90\",RESOLUTION=3840x2160,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/13000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=25702333,AVERAGE-BANDWIDTH=16502314,CODECS=\"mp4a.40.2,hvc1.2.4.H150.90\",RESOLUTION=3840x2160,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/16500k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=3790212,AVERAGE-BANDWIDTH=2621435,CODECS=\"mp4a.40.2,hvc1.2.4.L93.90\",RESOLUTION=1280x720,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/2500k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=5044473,AVERAGE-BANDWIDTH=3403100,CODECS=\"mp4a.40.2,hvc1.2.4.L93.90\",RESOLUTION=1280x720,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/3300k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=6986073,AVERAGE-BANDWIDTH=4717677,CODECS=\"mp4a.40.2,hvc1.2.4.L120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/4600k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=9533270,AVERAGE-BANDWIDTH=6100009,CODECS=\"mp4a.40.2,hvc1.2.4.L120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/6000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=12156778,AVERAGE-BANDWIDTH=7766087,CODECS=\"mp4a.40.2,hvc1.2.4.H120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/7700k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=13911387,AVERAGE-BANDWIDTH=8880895,CODECS=\"mp4a.40.2,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-128k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/8800k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=705826,AVERAGE-BANDWIDTH=514769,CODECS=\"mp4a.40.2,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-64k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/450k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=1352519,AVERAGE-BANDWIDTH=959558,CODECS=\"mp4a.40.2,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"aac-64k\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/900k/vod.m3u8\n\n#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=222552,CODECS=\"hvc1.2.4.L93.90\",RESOLUTION=1280x720,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/3300k/vod-iframe.m3u8\"\n#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=77758,CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"hdr10/unenc/900k/vod-iframe.m3u8\"\n\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"eac3\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"6\",URI=\"audio/unenc/ec3_256k/vod.m3u8\"\n\n#EXT-X-STREAM-INF:BANDWIDTH=2612376,AVERAGE-BANDWIDTH=1891332,CODECS=\"ec-3,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/1650k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=15939819,AVERAGE-BANDWIDTH=10186672,CODECS=\"ec-3,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/10000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=20783644,AVERAGE-BANDWIDTH=13195203,CODECS=\"ec-3,hvc1.2.4.H150.90\",RESOLUTION=3840x2160,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/13000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=25830920,AVERAGE-BANDWIDTH=16630901,CODECS=\"ec-3,hvc1.2.4.H150.90\",RESOLUTION=3840x2160,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/16500k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=3918799,AVERAGE-BANDWIDTH=2750022,CODECS=\"ec-3,hvc1.2.4.L93.90\",RESOLUTION=1280x720,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/2500k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=5173060,AVERAGE-BANDWIDTH=3531687,CODECS=\"ec-3,hvc1.2.4.L93.90\",RESOLUTION=1280x720,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/3300k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=7114660,AVERAGE-BANDWIDTH=4846264,CODECS=\"ec-3,hvc1.2.4.L120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/4600k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=9661857,AVERAGE-BANDWIDTH=6228596,CODECS=\"ec-3,hvc1.2.4.L120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/6000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=12285365,AVERAGE-BANDWIDTH=7894674,CODECS=\"ec-3,hvc1.2.4.H120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/7700k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=14039974,AVERAGE-BANDWIDTH=9009482,CODECS=\"ec-3,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/8800k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=898051,AVERAGE-BANDWIDTH=706994,CODECS=\"ec-3,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/450k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=1544744,AVERAGE-BANDWIDTH=1151783,CODECS=\"ec-3,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"eac3\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/900k/vod.m3u8\n\n\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"atmos\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"16/JOC\",URI=\"audio/unenc/atmos_1024k/vod.m3u8\"\n\n#EXT-X-STREAM-INF:BANDWIDTH=3380396,AVERAGE-BANDWIDTH=2659352,CODECS=\"ec-3,hvc1.2.4.L90.90\",RESOLUTION=960x540,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/1650k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=16707839,AVERAGE-BANDWIDTH=10954692,CODECS=\"ec-3,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/10000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=21551664,AVERAGE-BANDWIDTH=13963223,CODECS=\"ec-3,hvc1.2.4.H150.90\",RESOLUTION=3840x2160,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/13000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=26598940,AVERAGE-BANDWIDTH=17398921,CODECS=\"ec-3,hvc1.2.4.H150.90\",RESOLUTION=3840x2160,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/16500k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=4686819,AVERAGE-BANDWIDTH=3518042,CODECS=\"ec-3,hvc1.2.4.L93.90\",RESOLUTION=1280x720,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/2500k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=5941080,AVERAGE-BANDWIDTH=4299707,CODECS=\"ec-3,hvc1.2.4.L93.90\",RESOLUTION=1280x720,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/3300k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=7882680,AVERAGE-BANDWIDTH=5614284,CODECS=\"ec-3,hvc1.2.4.L120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/4600k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=10429877,AVERAGE-BANDWIDTH=6996616,CODECS=\"ec-3,hvc1.2.4.L120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/6000k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=13053385,AVERAGE-BANDWIDTH=8662694,CODECS=\"ec-3,hvc1.2.4.H120.90\",RESOLUTION=1920x1080,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/7700k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=14807994,AVERAGE-BANDWIDTH=9777502,CODECS=\"ec-3,hvc1.2.4.L150.90\",RESOLUTION=2560x1440,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/8800k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=1666071,AVERAGE-BANDWIDTH=1475014,CODECS=\"ec-3,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/450k/vod.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=2312764,AVERAGE-BANDWIDTH=1919803,CODECS=\"ec-3,hvc1.2.4.L63.90\",RESOLUTION=640x360,FRAME-RATE=23.97,VIDEO-RANGE=PQ,AUDIO=\"atmos\",CLOSED-CAPTIONS=NONE\nhdr10/unenc/900k/vod.m3u8\n\n";
 
 #[test]
 fn parses_hls() {
-    let parsed = parse_hls(HLS_STR).unwrap();
-    let master_playlist: MasterPlaylist = parsed.try_into().unwrap();
+    let master_playlist: MasterPlaylist = parse_hls(HLS_STR).unwrap();
 
     assert_eq!(master_playlist.variant_streams.len(), 36);
     assert_eq!(master_playlist.basic_tags.len(), 2);
     assert_eq!(master_playlist.media_tags.len(), 4);
     assert_eq!(master_playlist.i_frames.len(), 2);
 }
+
+#[test]
+fn example_playlist_follows_the_spec() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+
+    assert_eq!(validate(&master_playlist), vec![]);
+}
+
+#[test]
+fn parses_media_playlist() {
+    let parsed = parse_playlist(MEDIA_HLS_STR).unwrap();
+
+    match parsed {
+        Playlist::Media(media_playlist) => {
+            assert_eq!(media_playlist.target_duration, 10);
+            assert_eq!(media_playlist.segments.len(), 3);
+            assert_eq!(media_playlist.segments[2].duration(), 3.003);
+            assert_eq!(
+                media_playlist.segments[2].uri(),
+                "http://media.example.com/third.ts"
+            );
+            assert!(media_playlist.end_list);
+        }
+        Playlist::Master(_) => panic!("Expected a media playlist"),
+    }
+}
+
+#[test]
+fn detects_master_playlist() {
+    let parsed = parse_playlist(HLS_STR).unwrap();
+
+    assert!(matches!(parsed, Playlist::Master(_)));
+}
+
+#[test]
+fn rejects_media_playlists_as_master_playlists() {
+    let err = parse_hls(MEDIA_HLS_STR).unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::HLSTag { ref tag, ref message, ref location }
+            if tag == "EXT-X-TARGETDURATION"
+                && message == "EXT-X-TARGETDURATION is only allowed in media playlists"
+                && location.line == 3
+    ));
+}
+
+#[test]
+fn serialized_playlist_round_trips() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+
+    let serialized = master_playlist.to_string();
+
+    assert_eq!(parse_hls(&serialized).unwrap(), master_playlist);
+}
+
+#[test]
+fn writes_playlist_to_writer() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+    let mut written = Vec::new();
+
+    master_playlist.write_to(&mut written).unwrap();
+
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        master_playlist.to_string()
+    );
+}
+
+#[test]
+fn reports_location_of_unparseable_line() {
+    let hls_str =
+        "#EXTM3U\n#EXT-X-INDEPENDENT-SEGMENTS\n#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",oops\n";
+
+    match parse_hls(hls_str).unwrap_err() {
+        Error::Parse { location, .. } => {
+            assert_eq!(location.line, 3);
+            assert_eq!(location.column, 1);
+            assert!(location.snippet.starts_with("#EXT-X-MEDIA:"));
+        }
+        other => panic!("Expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn reports_location_of_invalid_tag() {
+    let hls_str = "#EXTM3U\n#EXT-X-INDEPENDENT-SEGMENTS\n\n#EXT-X-I-FRAME-STREAM-INF:CODECS=\"hvc1.2.4.L63.90\",RESOLUTION=640x360,VIDEO-RANGE=PQ,URI=\"iframe.m3u8\"\n";
+
+    match parse_hls(hls_str).unwrap_err() {
+        Error::HLSTag {
+            tag,
+            message,
+            location,
+        } => {
+            assert_eq!(tag, "EXT-X-I-FRAME-STREAM-INF");
+            assert_eq!(message, "HLS missing BANDWIDTH");
+            assert_eq!(location.line, 4);
+        }
+        other => panic!("Expected an HLS tag error, got {:?}", other),
+    }
+}
+
+#[test]
+fn selects_variant_by_bandwidth() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+
+    let best_under_limit = master_playlist
+        .variant_streams
+        .iter()
+        .filter(|variant| variant.bandwidth() <= 5_000_000)
+        .max_by_key(|variant| variant.bandwidth())
+        .unwrap();
+
+    assert_eq!(best_under_limit.bandwidth(), 4_686_819);
+    assert_eq!(best_under_limit.audio(), Some("atmos"));
+    assert_eq!(best_under_limit.uri(), "hdr10/unenc/2500k/vod.m3u8");
+}
+
+#[test]
+fn selects_variants_by_resolution() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+    let uhd = Resolution {
+        width: 3840,
+        height: 2160,
+    };
+
+    let uhd_variants: Vec<_> = master_playlist
+        .variant_streams
+        .iter()
+        .filter(|variant| variant.resolution() == Some(uhd))
+        .collect();
+
+    assert_eq!(uhd_variants.len(), 6);
+    assert!(uhd_variants
+        .iter()
+        .all(|variant| variant.video_range() == Some(&VideoRange::PQ)));
+}
+
+#[test]
+fn filters_variants_by_codec_level() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+
+    let up_to_level_4_1 = master_playlist
+        .variant_streams
+        .iter()
+        .filter_map(|variant| variant.codecs())
+        .flat_map(|codecs| codecs.formats())
+        .filter(|format| match format {
+            CodecFormat::Hevc(hevc) => {
+                hevc.profile == HevcProfile::Main10 && hevc.level <= Level::new(4, 1)
+            }
+            _ => false,
+        })
+        .count();
+
+    // Every variant is Main10, and only the 1440p and 2160p ones need level 5
+    assert_eq!(up_to_level_4_1, 24);
+}
+
+#[test]
+fn exposes_media_tag_and_i_frame_attributes() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+
+    let atmos = master_playlist
+        .media_tags
+        .iter()
+        .find(|media_tag| media_tag.group_id() == "atmos")
+        .unwrap();
+    assert_eq!(atmos.media_type(), MediaType::Audio);
+    assert_eq!(atmos.channels().unwrap().count, 16);
+    assert_eq!(atmos.language(), Some("en"));
+    assert!(atmos.default() && atmos.autoselect());
+
+    let smallest_i_frame = master_playlist
+        .i_frames
+        .iter()
+        .min_by_key(|i_frame| i_frame.bandwidth())
+        .unwrap();
+    assert_eq!(smallest_i_frame.resolution().unwrap().height, 360);
+    assert_eq!(smallest_i_frame.uri(), "hdr10/unenc/900k/vod-iframe.m3u8");
+}
+
+#[test]
+fn finds_immersive_audio_groups() {
+    let master_playlist = parse_hls(HLS_STR).unwrap();
+
+    let immersive: Vec<&str> = master_playlist
+        .media_tags
+        .iter()
+        .filter(|media_tag| media_tag.channels().is_some_and(Channels::is_immersive))
+        .map(|media_tag| media_tag.group_id())
+        .collect();
+    assert_eq!(immersive, vec!["atmos"]);
+
+    let mut by_channels: Vec<&MediaTag> = master_playlist.media_tags.iter().collect();
+    by_channels.sort_by_key(|media_tag| media_tag.channels().map(|channels| channels.count));
+    assert_eq!(by_channels.last().unwrap().group_id(), "atmos");
+}
+
+#[test]
+fn parses_variant_streams_with_only_required_attributes() {
+    let hls_str = "#EXTM3U\n#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"English\",URI=\"subs/en.m3u8\"\n#EXT-X-STREAM-INF:BANDWIDTH=1280000\nlow/video.m3u8\n#EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=86000,URI=\"low/iframe.m3u8\"\n";
+
+    let master_playlist = parse_hls(hls_str).unwrap();
+
+    let variant = &master_playlist.variant_streams[0];
+    assert_eq!(variant.bandwidth(), 1_280_000);
+    assert_eq!(variant.average_bandwidth(), None);
+    assert_eq!(variant.codecs(), None);
+    assert_eq!(master_playlist.media_tags[0].language(), None);
+    assert_eq!(master_playlist.i_frames[0].resolution(), None);
+    assert_eq!(
+        parse_hls(&master_playlist.to_string()).unwrap(),
+        master_playlist
+    );
+}
+
+#[test]
+fn preserves_vendor_tags_through_serialization() {
+    let hls_str = "#EXTM3U\n#EXT-X-INDEPENDENT-SEGMENTS\n#EXT-X-BITMOVIN-DRM:KEY-ID=\"abc\",SYSTEM=widevine\n#EXT-X-STREAM-INF:BANDWIDTH=1280000\nlow/video.m3u8\n#EXT-X-CUE-OUT:30\n#EXT-X-STREAM-INF:BANDWIDTH=2560000\nmid/video.m3u8\n#EXT-X-CUE-IN\n";
+
+    let master_playlist = parse_hls(hls_str).unwrap();
+    let reparsed = parse_hls(&master_playlist.to_string()).unwrap();
+
+    let unknown: Vec<_> = master_playlist
+        .unknown_tags
+        .iter()
+        .map(|tag| (tag.name.as_str(), tag.value.as_deref(), tag.line))
+        .collect();
+    assert_eq!(
+        unknown,
+        vec![
+            (
+                "EXT-X-BITMOVIN-DRM",
+                Some("KEY-ID=\"abc\",SYSTEM=widevine"),
+                3
+            ),
+            ("EXT-X-CUE-OUT", Some("30"), 6),
+            ("EXT-X-CUE-IN", None, 9),
+        ]
+    );
+    assert_eq!(
+        master_playlist.basic_tags,
+        vec!["EXT-X-INDEPENDENT-SEGMENTS", "EXTM3U"]
+    );
+    assert_eq!(master_playlist.to_string(), hls_str);
+    assert_eq!(reparsed, master_playlist);
+}
+
+#[test]
+fn parses_session_data_and_keys() {
+    let hls_str = "#EXTM3U\n#EXT-X-SESSION-DATA:DATA-ID=\"com.example.title\",VALUE=\"Example\",LANGUAGE=\"en\"\n#EXT-X-SESSION-DATA:DATA-ID=\"com.example.lyrics\",URI=\"lyrics.json\"\n#EXT-X-SESSION-KEY:METHOD=SAMPLE-AES,URI=\"skd://key\",IV=0x9c7db8778570d05c3177c349fd9236aa,KEYFORMAT=\"com.apple.streamingkeydelivery\",KEYFORMATVERSIONS=\"1\"\n#EXT-X-STREAM-INF:BANDWIDTH=1280000\nlow/video.m3u8\n";
+
+    let master_playlist = parse_hls(hls_str).unwrap();
+
+    let session_data: Vec<_> = master_playlist
+        .session_data
+        .iter()
+        .map(|data| (data.data_id(), data.value(), data.uri()))
+        .collect();
+    assert_eq!(
+        session_data,
+        vec![
+            ("com.example.title", Some("Example"), None),
+            ("com.example.lyrics", None, Some("lyrics.json")),
+        ]
+    );
+    let session_key = &master_playlist.session_keys[0];
+    assert_eq!(session_key.method(), KeyMethod::SampleAes);
+    assert_eq!(
+        session_key.iv(),
+        Some(InitializationVector(0x9c7db8778570d05c3177c349fd9236aa))
+    );
+    assert_eq!(session_key.key_format_versions(), Some(&[1][..]));
+    assert!(master_playlist.unknown_tags.is_empty());
+    assert_eq!(validate(&master_playlist), vec![]);
+
+    let reparsed = parse_hls(&master_playlist.to_string()).unwrap();
+    assert_eq!(reparsed.session_data, master_playlist.session_data);
+    assert_eq!(reparsed.session_keys, master_playlist.session_keys);
+}
+
+#[test]
+fn rejects_session_keys_without_encryption() {
+    let hls_str = "#EXTM3U\n#EXT-X-SESSION-KEY:METHOD=NONE\n#EXT-X-STREAM-INF:BANDWIDTH=1280000\nlow/video.m3u8\n";
+
+    let err = parse_hls(hls_str).unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::HLSTag { ref tag, ref message, ref location }
+            if tag == "EXT-X-SESSION-KEY"
+                && message == "EXT-X-SESSION-KEY METHOD must not be NONE"
+                && location.line == 2
+    ));
+}
+
+#[test]
+fn exposes_the_key_and_map_of_each_segment() {
+    let media_str = "#EXTM3U\n#EXT-X-VERSION:6\n#EXT-X-TARGETDURATION:10\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXT-X-KEY:METHOD=AES-128,URI=\"https://keys.example.com/1\",IV=0x9c7db8778570d05c3177c349fd9236aa\n#EXTINF:9.009,\n0.mp4\n#EXTINF:9.009,\n#EXT-X-KEY:METHOD=AES-128,URI=\"https://keys.example.com/2\"\n1.mp4\n";
+
+    let media_playlist = match parse_playlist(media_str).unwrap() {
+        Playlist::Media(media_playlist) => media_playlist,
+        Playlist::Master(_) => panic!("Expected a media playlist"),
+    };
+
+    let keys: Vec<_> = media_playlist
+        .segments
+        .iter()
+        .map(|segment| {
+            let key = &segment.keys()[0];
+            (key.uri(), key.iv(), segment.map().map(|map| map.uri()))
+        })
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            (
+                Some("https://keys.example.com/1"),
+                Some(InitializationVector(0x9c7db8778570d05c3177c349fd9236aa)),
+                Some("init.mp4")
+            ),
+            (Some("https://keys.example.com/2"), None, Some("init.mp4")),
+        ]
+    );
+    assert!(media_playlist.unknown_tags.is_empty());
+}
+
+#[test]
+fn resolves_byte_ranges_to_absolute_positions() {
+    let media_str = "#EXTM3U\n#EXT-X-VERSION:4\n#EXT-X-TARGETDURATION:10\n#EXT-X-MAP:URI=\"main.mp4\",BYTERANGE=\"720@0\"\n#EXTINF:9.009,\n#EXT-X-BYTERANGE:75232@720\nmain.mp4\n#EXTINF:9.009,\n#EXT-X-BYTERANGE:82112\nmain.mp4\n#EXT-X-BYTERANGE:69864\n#EXTINF:3.003,\nmain.mp4\n#EXTINF:3.003,\nextra.mp4\n";
+
+    let media_playlist = match parse_playlist(media_str).unwrap() {
+        Playlist::Media(media_playlist) => media_playlist,
+        Playlist::Master(_) => panic!("Expected a media playlist"),
+    };
+
+    let ranges: Vec<_> = media_playlist
+        .segments
+        .iter()
+        .map(|segment| segment.byte_range())
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![
+            Some((720, 75232)),
+            Some((75952, 82112)),
+            Some((158064, 69864)),
+            None
+        ]
+    );
+    assert_eq!(
+        media_playlist.segments[0].map().unwrap().range(),
+        Some((0, 720))
+    );
+}
+
+#[test]
+fn rejects_implicit_byte_range_offsets_after_a_different_resource() {
+    let media_str = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:9.009,\n#EXT-X-BYTERANGE:75232@0\nfirst.mp4\n#EXTINF:9.009,\n#EXT-X-BYTERANGE:82112\nsecond.mp4\n";
+
+    let err = parse_playlist(media_str).unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::HLSTag { ref message, ref location, .. }
+            if message == "EXT-X-BYTERANGE for second.mp4 has no offset but the previous sub-range is of first.mp4"
+                && location.line == 6
+    ));
+}
+
+#[test]
+fn keeps_media_tags_with_unknown_values_when_lenient() {
+    let media_str = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXT-X-KEY:METHOD=SAMPLE-AES-CBCS,URI=\"key.bin\"\n#EXTINF:9.009,\nfirst.ts\n";
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+
+    let strict = parse_playlist(media_str);
+    let media_playlist = match parse_playlist_with_options(media_str, &options).unwrap() {
+        Playlist::Media(media_playlist) => media_playlist,
+        Playlist::Master(_) => panic!("Expected a media playlist"),
+    };
+
+    assert!(matches!(
+        strict,
+        Err(Error::HLSTag { tag, .. }) if tag == "EXT-X-KEY"
+    ));
+    assert!(media_playlist.segments[0].keys().is_empty());
+    assert_eq!(media_playlist.unknown_tags[0].name, "EXT-X-KEY");
+    let messages: Vec<String> = validate_media_playlist(&media_playlist)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        messages,
+        vec!["warning[unknown-value]: #EXT-X-KEY on line 3 has a value this parser doesn't know about"]
+    );
+}
+
+#[test]
+fn attaches_low_latency_parts_to_their_segments() {
+    let media_str = "#EXTM3U\n#EXT-X-VERSION:9\n#EXT-X-TARGETDURATION:4\n#EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES,CAN-SKIP-UNTIL=24.0,PART-HOLD-BACK=1.002\n#EXT-X-PART-INF:PART-TARGET=0.334\n#EXT-X-MEDIA-SEQUENCE:266\n#EXT-X-SKIP:SKIPPED-SEGMENTS=3\n#EXT-X-PART:DURATION=0.334,INDEPENDENT=YES,URI=\"filePart266.0.mp4\"\n#EXT-X-PART:DURATION=0.334,URI=\"filePart266.1.mp4\"\n#EXTINF:0.668,\nfileSequence266.mp4\n#EXT-X-PART:DURATION=0.334,INDEPENDENT=YES,URI=\"filePart267.0.mp4\"\n#EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"filePart267.1.mp4\"\n#EXT-X-RENDITION-REPORT:URI=\"../1M/waitForMSN.php\",LAST-MSN=267,LAST-PART=0\n";
+
+    let media_playlist = match parse_playlist(media_str).unwrap() {
+        Playlist::Media(media_playlist) => media_playlist,
+        Playlist::Master(_) => panic!("Expected a media playlist"),
+    };
+
+    let parts: Vec<(&str, bool)> = media_playlist.segments[0]
+        .parts()
+        .iter()
+        .map(|part| (part.uri(), part.independent()))
+        .collect();
+    assert_eq!(
+        parts,
+        vec![("filePart266.0.mp4", true), ("filePart266.1.mp4", false)]
+    );
+    assert_eq!(media_playlist.parts[0].uri(), "filePart267.0.mp4");
+    assert_eq!(media_playlist.part_target, Some(0.334));
+    let server_control = media_playlist.server_control.unwrap();
+    assert_eq!(server_control.can_block_reload, Some(true));
+    assert_eq!(server_control.can_skip_until, Some(24.0));
+    assert_eq!(media_playlist.skip.as_ref().unwrap().skipped_segments, 3);
+    let preload_hint = &media_playlist.preload_hints[0];
+    assert_eq!(preload_hint.hint_type(), PreloadHintType::Part);
+    assert_eq!(preload_hint.byte_range_start(), 0);
+    let rendition_report = &media_playlist.rendition_reports[0];
+    assert_eq!(rendition_report.last_msn(), Some(267));
+    assert_eq!(rendition_report.last_part(), Some(0));
+    assert!(media_playlist.unknown_tags.is_empty());
+    assert_eq!(validate_media_playlist(&media_playlist), vec![]);
+}
+
+#[test]
+fn decodes_scte35_ad_breaks_in_date_ranges() {
+    let media_str = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXT-X-PROGRAM-DATE-TIME:2014-03-05T11:14:00Z\n#EXT-X-DATERANGE:ID=\"splice-4800008F\",CLASS=\"com.example.ad\",START-DATE=\"2014-03-05T11:15:00Z\",PLANNED-DURATION=60.0,X-COM-EXAMPLE-AD-ID=\"XYZ123\",SCTE35-OUT=0xFC302500000000000000FFF014054800008F7FEFFE7369C02EFE005265C0000001010000FF35D3F4\n#EXTINF:10.0,\nfirst.ts\n";
+
+    let media_playlist = match parse_playlist(media_str).unwrap() {
+        Playlist::Media(media_playlist) => media_playlist,
+        Playlist::Master(_) => panic!("Expected a media playlist"),
+    };
+
+    let date_range = &media_playlist.date_ranges[0];
+    assert_eq!(date_range.id(), "splice-4800008F");
+    assert_eq!(date_range.class(), Some("com.example.ad"));
+    assert_eq!(date_range.start_date(), Some("2014-03-05T11:15:00Z"));
+    assert_eq!(date_range.planned_duration(), Some(60.0));
+    assert_eq!(date_range.duration(), None);
+    assert_eq!(
+        date_range.client_attributes()["X-COM-EXAMPLE-AD-ID"],
+        "XYZ123"
+    );
+    let splice_insert = match &date_range.scte35_out().unwrap().command {
+        SpliceCommand::Insert(splice_insert) => splice_insert,
+        command => panic!("Expected a splice_insert, found {:?}", command),
+    };
+    assert_eq!(splice_insert.event_id, 0x4800008F);
+    assert!(splice_insert.out_of_network);
+    assert_eq!(splice_insert.pts_time, Some(0x7369C02E));
+    assert_eq!(
+        splice_insert.break_duration,
+        Some(BreakDuration {
+            auto_return: true,
+            duration: 5_400_000
+        })
+    );
+    assert_eq!(
+        (splice_insert.avail_num, splice_insert.avails_expected),
+        (1, 1)
+    );
+}
+
+/// Serves a single HTTP response on a local port, returning the URL to request and a handle
+/// that yields the request headers the server received.
+fn serve_once(status: &'static str, body: &'static str) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/master.m3u8", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let request_lines: Vec<String> = BufReader::new(&mut stream)
+            .lines()
+            .map(|line| line.unwrap())
+            .take_while(|line| !line.is_empty())
+            .collect();
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+        .unwrap();
+        request_lines
+    });
+
+    (url, handle)
+}
+
+#[test]
+fn parses_hls_from_url() {
+    let (url, server) = serve_once("200 OK", HLS_STR);
+
+    let master_playlist = parse_hls_from_url(&url).unwrap();
+
+    server.join().unwrap();
+    assert_eq!(master_playlist, parse_hls(HLS_STR).unwrap());
+}
+
+#[test]
+fn substitutes_variables_through_master_and_media_playlists() {
+    let (url, server) = serve_once(
+        "200 OK",
+        "#EXTM3U\n#EXT-X-DEFINE:QUERYPARAM=\"token\"\n#EXT-X-DEFINE:NAME=\"cdn\",VALUE=\"https://cdn.example.com\"\n#EXT-X-STREAM-INF:BANDWIDTH=1280000\n{$cdn}/low.m3u8?token={$token}\n",
+    );
+
+    let master_playlist = parse_hls_from_url(&format!("{}?token=abc", url)).unwrap();
+    server.join().unwrap();
+
+    assert_eq!(
+        master_playlist.variant_streams[0].uri(),
+        "https://cdn.example.com/low.m3u8?token=abc"
+    );
+    assert_eq!(master_playlist.variables["token"], "abc");
+    let serialized = master_playlist.to_string();
+    assert!(serialized.contains("\n#EXT-X-DEFINE:QUERYPARAM=\"token\"\n"));
+    let reparse_options = ParseOptions {
+        url: Some(format!("{}?token=abc", url)),
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        parse_hls_with_options(&serialized, &reparse_options).unwrap(),
+        master_playlist
+    );
+
+    let options = ParseOptions {
+        imported_variables: master_playlist.variables.clone(),
+        ..ParseOptions::default()
+    };
+    let media_str = "#EXTM3U\n#EXT-X-DEFINE:IMPORT=\"cdn\"\n#EXT-X-TARGETDURATION:10\n#EXTINF:9.009,\n{$cdn}/0.ts\n";
+    match parse_playlist_with_options(media_str, &options).unwrap() {
+        Playlist::Media(media_playlist) => {
+            assert_eq!(
+                media_playlist.segments[0].uri(),
+                "https://cdn.example.com/0.ts"
+            )
+        }
+        Playlist::Master(_) => panic!("Expected a media playlist"),
+    }
+    assert!(matches!(
+        parse_playlist(media_str),
+        Err(Error::HLSTag { tag, .. }) if tag == "EXT-X-DEFINE"
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn steers_variant_streams_with_a_manifest_from_the_steering_server() {
+    let hls_str = "#EXTM3U\n#EXT-X-CONTENT-STEERING:SERVER-URI=\"https://steering.example.com/manifest.json\",PATHWAY-ID=\"cdn-a\"\n#EXT-X-STREAM-INF:BANDWIDTH=1280000,PATHWAY-ID=\"cdn-a\"\nhttps://cdn-a.example.com/low.m3u8\n#EXT-X-STREAM-INF:BANDWIDTH=1280000,PATHWAY-ID=\"cdn-b\"\nhttps://cdn-b.example.com/low.m3u8\n";
+    let master_playlist = parse_hls(hls_str).unwrap();
+    let content_steering = master_playlist.content_steering.as_ref().unwrap();
+    assert_eq!(
+        content_steering.server_uri,
+        "https://steering.example.com/manifest.json"
+    );
+    assert_eq!(content_steering.pathway_id.as_deref(), Some("cdn-a"));
+
+    // Stands in for the steering server in the SERVER-URI
+    let (url, server) = serve_once(
+        "200 OK",
+        r#"{"VERSION": 1, "TTL": 300, "RELOAD-URI": "manifest.json?session=1", "PATHWAY-PRIORITY": ["cdn-b", "cdn-a"]}"#,
+    );
+    let manifest = BlockingApiClient::new()
+        .unwrap()
+        .get_steering_manifest(&url)
+        .unwrap();
+    server.join().unwrap();
+
+    let steered = master_playlist.steered_variant_streams(&manifest, None);
+    let uris: Vec<&str> = steered
+        .iter()
+        .map(|variant_stream| variant_stream.uri())
+        .collect();
+    assert_eq!(uris, vec!["https://cdn-b.example.com/low.m3u8"]);
+    assert_eq!(manifest.ttl, 300);
+    assert_eq!(
+        parse_hls(&master_playlist.to_string()).unwrap(),
+        master_playlist
+    );
+}
+
+#[test]
+fn sends_configured_headers() {
+    let (url, server) = serve_once("200 OK", MEDIA_HLS_STR);
+    let api_client = BlockingApiClient::builder()
+        .header("Authorization", "Bearer token")
+        .unwrap()
+        .header("User-Agent", "hls_parser-test")
+        .unwrap()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let body = api_client.get_playlist(&url).unwrap();
+
+    let request_lines = server.join().unwrap();
+    assert_eq!(body, MEDIA_HLS_STR);
+    assert!(request_lines
+        .iter()
+        .any(|line| line.eq_ignore_ascii_case("authorization: Bearer token")));
+    assert!(request_lines
+        .iter()
+        .any(|line| line.eq_ignore_ascii_case("user-agent: hls_parser-test")));
+}
+
+#[test]
+fn parses_hls_from_url_with_a_configured_client() {
+    let (url, server) = serve_once("200 OK", HLS_STR);
+    let api_client = BlockingApiClient::builder()
+        .header("Authorization", "Bearer token")
+        .unwrap()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let master_playlist = parse_hls_from_url_with_client(&url, &api_client).unwrap();
+
+    let request_lines = server.join().unwrap();
+    assert_eq!(master_playlist, parse_hls(HLS_STR).unwrap());
+    assert!(request_lines
+        .iter()
+        .any(|line| line.eq_ignore_ascii_case("authorization: Bearer token")));
+}
+
+#[test]
+fn reports_http_error_status() {
+    let (url, server) = serve_once("404 Not Found", "");
+
+    let result = parse_hls_from_url(&url);
+
+    server.join().unwrap();
+    assert!(matches!(result.unwrap_err(), Error::HTTP(_)));
+}